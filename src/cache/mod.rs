@@ -0,0 +1,82 @@
+use lru::LruCache;
+use std::fmt::Display;
+use std::hash::Hash;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{event, Level};
+
+#[cfg(test)]
+mod tests;
+
+/// A bounded, thread-safe cache where each entry expires after a fixed time to live. When the
+/// cache is full, the least recently used entry is evicted to make space for a new one.
+pub struct TtlCache<K: Hash + Eq, V> {
+    ttl: Duration,
+    entries: Mutex<LruCache<K, CacheEntry<V>>>,
+}
+
+struct CacheEntry<V> {
+    inserted: Instant,
+    value: V,
+}
+
+impl<K: Hash + Eq, V> TtlCache<K, V> {
+    /// # Arguments
+    /// * `capacity` - The maximum number of entries that the cache will hold.
+    /// * `ttl` - How long an entry remains valid after it is inserted.
+    pub fn new(capacity: NonZeroUsize, ttl: Duration) -> Self {
+        TtlCache {
+            ttl,
+            entries: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+}
+
+impl<K, V> TtlCache<K, V>
+where
+    K: Hash + Eq + Display,
+    V: Clone,
+{
+    /// Get the value for a key, if it is present and has not expired.
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.get_at(key, Instant::now())
+    }
+
+    /// Insert a value, replacing any existing value for the key.
+    pub fn insert(&self, key: K, value: V) {
+        self.insert_at(key, value, Instant::now())
+    }
+
+    fn get_at(&self, key: &K, now: Instant) -> Option<V> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some(CacheEntry { inserted, value }) if now.duration_since(*inserted) < self.ttl => {
+                event!(Level::DEBUG, message = "Cache hit.", %key);
+                Some(value.clone())
+            }
+            Some(_) => {
+                event!(Level::DEBUG, message = "Cache entry has expired.", %key);
+                entries.pop(key);
+                None
+            }
+            None => {
+                event!(Level::DEBUG, message = "Cache miss.", %key);
+                None
+            }
+        }
+    }
+
+    fn insert_at(&self, key: K, value: V, now: Instant) {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = CacheEntry {
+            inserted: now,
+            value,
+        };
+        if let Some((evicted, _)) = entries.push(key, entry) {
+            if !entries.contains(&evicted) {
+                event!(Level::DEBUG, message = "Evicted least recently used cache entry.", key = %evicted);
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use crate::cache::TtlCache;
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+
+const TTL: Duration = Duration::from_secs(60);
+
+fn make_cache(capacity: usize) -> TtlCache<String, i32> {
+    TtlCache::new(NonZeroUsize::new(capacity).unwrap(), TTL)
+}
+
+#[test]
+fn get_inserted() {
+    let cache = make_cache(2);
+    cache.insert("a".to_string(), 1);
+
+    assert_eq!(cache.get(&"a".to_string()), Some(1));
+    assert_eq!(cache.get(&"b".to_string()), None);
+}
+
+#[test]
+fn replace_entry() {
+    let cache = make_cache(2);
+    cache.insert("a".to_string(), 1);
+    cache.insert("a".to_string(), 2);
+
+    assert_eq!(cache.get(&"a".to_string()), Some(2));
+}
+
+#[test]
+fn entries_expire() {
+    let cache = make_cache(2);
+    let start = Instant::now();
+    cache.insert_at("a".to_string(), 1, start);
+
+    assert_eq!(cache.get_at(&"a".to_string(), start + TTL / 2), Some(1));
+    assert_eq!(cache.get_at(&"a".to_string(), start + TTL), None);
+    assert_eq!(cache.get_at(&"a".to_string(), start), None);
+}
+
+#[test]
+fn evict_least_recently_used() {
+    let cache = make_cache(2);
+    cache.insert("a".to_string(), 1);
+    cache.insert("b".to_string(), 2);
+
+    assert_eq!(cache.get(&"a".to_string()), Some(1));
+
+    cache.insert("c".to_string(), 3);
+
+    assert_eq!(cache.get(&"a".to_string()), Some(1));
+    assert_eq!(cache.get(&"b".to_string()), None);
+    assert_eq!(cache.get(&"c".to_string()), Some(3));
+}
@@ -0,0 +1,89 @@
+use crate::services::translation::{
+    DynTranslationService, Translated, TranslationError, TranslationService,
+};
+use futures::future::BoxFuture;
+use futures::{FutureExt, TryFutureExt};
+use tracing::{event, Level};
+
+#[cfg(test)]
+mod tests;
+
+/// Decides whether a failed step of a chain falls through to the next translator.
+pub type FallThrough = fn(&TranslationError) -> bool;
+
+struct Step {
+    name: String,
+    service: DynTranslationService,
+    fall_through: FallThrough,
+}
+
+/// Translation service that tries an ordered list of translators, reporting which of them produced
+/// the translation. If a translator fails, the next is only tried if the step allows it for the
+/// error. Otherwise (or if it was the last translator) the error is returned.
+#[derive(Default)]
+pub struct TranslatorChain {
+    steps: Vec<Step>,
+}
+
+impl TranslatorChain {
+    /// Add a translator to the end of the chain.
+    ///
+    /// # Arguments
+    /// * `name` - Reported as the translator for the translations that it produces.
+    /// * `service` - The translator.
+    /// * `fall_through` - For which errors from the translator the next translator is tried.
+    pub fn then<T>(mut self, name: &str, service: T, fall_through: FallThrough) -> Self
+    where
+        T: TranslationService + Send + Sync + 'static,
+    {
+        self.steps.push(Step {
+            name: name.to_string(),
+            service: Box::new(service),
+            fall_through,
+        });
+        self
+    }
+}
+
+impl TranslationService for TranslatorChain {
+    fn attempt_translation<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<String, TranslationError>> {
+        self.attributed_translation(text)
+            .map_ok(|translated| translated.text)
+            .boxed()
+    }
+
+    fn primary_translator(&self) -> Option<&str> {
+        self.steps.first().map(|step| step.name.as_str())
+    }
+
+    fn attributed_translation<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<Translated, TranslationError>> {
+        async move {
+            for (i, step) in self.steps.iter().enumerate() {
+                match step.service.attempt_translation(text).await {
+                    Ok(text) => {
+                        return Ok(Translated {
+                            text,
+                            translator: Some(step.name.clone()),
+                        })
+                    }
+                    Err(error) => {
+                        let is_last = i + 1 == self.steps.len();
+                        if is_last || !(step.fall_through)(&error) {
+                            return Err(error);
+                        }
+                        event!(Level::WARN, message = "Trying the next translator.", translator = %step.name, %error);
+                    }
+                }
+            }
+            // There are no translators in the chain.
+            Err(TranslationError::ServiceUnavailable)
+        }
+        .boxed()
+    }
+}
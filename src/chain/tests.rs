@@ -0,0 +1,150 @@
+use crate::chain::TranslatorChain;
+use crate::services::translation::{Translated, TranslationError, TranslationService};
+use futures::future::{ready, BoxFuture};
+use futures::FutureExt;
+
+struct FixedTranslationService(Result<&'static str, TranslationError>);
+
+impl TranslationService for FixedTranslationService {
+    fn attempt_translation<'a>(
+        &'a self,
+        _text: &'a str,
+    ) -> BoxFuture<'a, Result<String, TranslationError>> {
+        ready(self.0.clone().map(str::to_string)).boxed()
+    }
+}
+
+fn translated(text: &str, translator: &str) -> Translated {
+    Translated {
+        text: text.to_string(),
+        translator: Some(translator.to_string()),
+    }
+}
+
+#[tokio::test]
+async fn first_translator_used_when_successful() {
+    let chain = TranslatorChain::default()
+        .then(
+            "first",
+            FixedTranslationService(Ok("first")),
+            TranslationError::is_transient,
+        )
+        .then(
+            "second",
+            FixedTranslationService(Ok("second")),
+            TranslationError::is_transient,
+        );
+    assert_eq!(
+        chain.attributed_translation("text").await,
+        Ok(translated("first", "first"))
+    );
+    assert_eq!(
+        chain.attempt_translation("text").await,
+        Ok("first".to_string())
+    );
+}
+
+#[tokio::test]
+async fn falls_through_transient_errors() {
+    let chain = TranslatorChain::default()
+        .then(
+            "paid",
+            FixedTranslationService(Err(TranslationError::RateLimited(Default::default()))),
+            TranslationError::is_transient,
+        )
+        .then(
+            "free",
+            FixedTranslationService(Err(TranslationError::ServiceUnavailable)),
+            TranslationError::is_transient,
+        )
+        .then(
+            "offline",
+            FixedTranslationService(Ok("offline")),
+            TranslationError::is_transient,
+        );
+    assert_eq!(
+        chain.attributed_translation("text").await,
+        Ok(translated("offline", "offline"))
+    );
+}
+
+#[tokio::test]
+async fn stops_at_failed_translation() {
+    let chain = TranslatorChain::default()
+        .then(
+            "first",
+            FixedTranslationService(Err(TranslationError::TranslationFailed)),
+            TranslationError::is_transient,
+        )
+        .then(
+            "second",
+            FixedTranslationService(Ok("second")),
+            TranslationError::is_transient,
+        );
+    assert_eq!(
+        chain.attributed_translation("text").await,
+        Err(TranslationError::TranslationFailed)
+    );
+
+    let chain = TranslatorChain::default()
+        .then(
+            "first",
+            FixedTranslationService(Err(TranslationError::TranslationFailed)),
+            |_| true,
+        )
+        .then(
+            "second",
+            FixedTranslationService(Ok("second")),
+            TranslationError::is_transient,
+        );
+    assert_eq!(
+        chain.attributed_translation("text").await,
+        Ok(translated("second", "second"))
+    );
+}
+
+#[tokio::test]
+async fn last_error_returned() {
+    let chain = TranslatorChain::default()
+        .then(
+            "first",
+            FixedTranslationService(Err(TranslationError::ServiceUnavailable)),
+            TranslationError::is_transient,
+        )
+        .then(
+            "second",
+            FixedTranslationService(Err(TranslationError::Timeout)),
+            TranslationError::is_transient,
+        );
+    assert_eq!(
+        chain.attributed_translation("text").await,
+        Err(TranslationError::Timeout)
+    );
+
+    assert_eq!(
+        TranslatorChain::default()
+            .attributed_translation("text")
+            .await,
+        Err(TranslationError::ServiceUnavailable)
+    );
+}
+
+#[test]
+fn later_translators_are_fallbacks() {
+    let chain = TranslatorChain::default()
+        .then(
+            "first",
+            FixedTranslationService(Ok("first")),
+            TranslationError::is_transient,
+        )
+        .then(
+            "second",
+            FixedTranslationService(Ok("second")),
+            TranslationError::is_transient,
+        );
+    assert_eq!(chain.primary_translator(), Some("first"));
+    assert!(!chain.is_fallback(&translated("text", "first")));
+    assert!(chain.is_fallback(&translated("text", "second")));
+
+    assert_eq!(TranslatorChain::default().primary_translator(), None);
+}
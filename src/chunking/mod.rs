@@ -0,0 +1,243 @@
+use crate::cache::TtlCache;
+use crate::services::translation::{TranslationError, TranslationService};
+use futures::future::BoxFuture;
+use futures::{FutureExt, StreamExt};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use tracing::{event, Level};
+
+#[cfg(test)]
+mod tests;
+
+lazy_static! {
+    /// The end of a sentence: terminal punctuation, possibly followed by closing quotes or
+    /// brackets, then whitespace.
+    static ref SENTENCE_END: Regex = Regex::new(r#"[.!?…]+["'”’)\]]*\s+"#).unwrap();
+}
+
+/// Configuration for splitting long text into chunks for translation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkingConfig {
+    /// The maximum length (in characters) of the text sent to the translator at once.
+    pub max_chars: NonZeroUsize,
+    /// The maximum number of chunks of a text to translate concurrently.
+    pub concurrency: NonZeroUsize,
+}
+
+/// Wraps a translation service so that text that is longer than the maximum length is split into
+/// chunks, at the ends of sentences where possible. The chunks are translated separately and the
+/// translations are joined with the original spacing. The translation of each sentence is cached
+/// so that a sentence that appears in more than one text is only translated once: only the
+/// sentences that are not cached are sent to the translator.
+pub struct ChunkingTranslationService<S> {
+    inner: S,
+    config: ChunkingConfig,
+    cache: TtlCache<String, String>,
+}
+
+/// A part of the text being translated.
+enum Part {
+    /// A sentence for which the translation is cached.
+    Cached(Range<usize>, String),
+    /// Consecutive sentences to be translated together.
+    Chunk(Vec<Range<usize>>),
+}
+
+impl<S> ChunkingTranslationService<S> {
+    /// # Arguments
+    /// * `inner` - The underlying translation service.
+    /// * `config` - How to split the text.
+    /// * `cache` - Cache for the translations of the sentences.
+    pub fn new(inner: S, config: ChunkingConfig, cache: TtlCache<String, String>) -> Self {
+        ChunkingTranslationService {
+            inner,
+            config,
+            cache,
+        }
+    }
+
+    /// Translate a chunk of consecutive sentences of the text, caching the translation of each.
+    async fn translate_chunk(
+        &self,
+        text: &str,
+        sentences: &[Range<usize>],
+    ) -> Result<String, TranslationError>
+    where
+        S: TranslationService,
+    {
+        let translated = self
+            .inner
+            .attempt_translation(&text[span(sentences)])
+            .await?;
+        if let [sentence] = sentences {
+            self.cache
+                .insert(text[sentence.clone()].to_string(), translated.clone());
+        } else {
+            // The translation can only be attributed to the sentences if it has as many.
+            let translated_sentences = segments(&translated, usize::MAX);
+            if translated_sentences.len() == sentences.len() {
+                for (sentence, translation) in sentences.iter().zip(translated_sentences) {
+                    self.cache.insert(
+                        text[sentence.clone()].to_string(),
+                        translated[translation].to_string(),
+                    );
+                }
+            } else {
+                event!(
+                    Level::DEBUG,
+                    message = "The sentences of the translation do not match those of the text.",
+                    sentences = sentences.len(),
+                    translated = translated_sentences.len()
+                );
+            }
+        }
+        Ok(translated)
+    }
+}
+
+impl<S> TranslationService for ChunkingTranslationService<S>
+where
+    S: TranslationService + Send + Sync,
+{
+    fn attempt_translation<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<String, TranslationError>> {
+        async move {
+            let max_chars = self.config.max_chars.get();
+            let mut parts = vec![];
+            let mut uncached = vec![];
+            for sentence in segments(text, max_chars) {
+                match self.cache.get(&text[sentence.clone()].to_string()) {
+                    Some(translation) => {
+                        parts.extend(
+                            pack(text, &uncached, max_chars)
+                                .into_iter()
+                                .map(Part::Chunk),
+                        );
+                        uncached.clear();
+                        parts.push(Part::Cached(sentence, translation));
+                    }
+                    None => uncached.push(sentence),
+                }
+            }
+            parts.extend(
+                pack(text, &uncached, max_chars)
+                    .into_iter()
+                    .map(Part::Chunk),
+            );
+
+            let chunks = parts
+                .iter()
+                .filter(|part| matches!(part, Part::Chunk(_)))
+                .count();
+            if chunks > 1 {
+                event!(
+                    Level::DEBUG,
+                    message = "Translating the text in chunks.",
+                    chunks
+                );
+            }
+            // The translations are kept in the order of the chunks.
+            let requests = parts
+                .iter()
+                .filter_map(|part| match part {
+                    Part::Chunk(sentences) => Some(self.translate_chunk(text, sentences).boxed()),
+                    Part::Cached(..) => None,
+                })
+                .collect::<Vec<_>>();
+            let mut translations = futures::stream::iter(requests)
+                .buffered(self.config.concurrency.get())
+                .collect::<Vec<_>>()
+                .await
+                .into_iter();
+
+            let mut translated = String::with_capacity(text.len());
+            let mut last = 0;
+            for part in parts {
+                let (range, translation) = match part {
+                    Part::Cached(range, translation) => (range, translation),
+                    Part::Chunk(sentences) => (span(&sentences), translations.next().unwrap()?),
+                };
+                translated.push_str(&text[last..range.start]);
+                translated.push_str(translation.as_str());
+                last = range.end;
+            }
+            translated.push_str(&text[last..]);
+            Ok(translated)
+        }
+        .boxed()
+    }
+}
+
+/// Split text into its sentences, without the whitespace around them. Sentences longer than
+/// `max_chars` characters are split between words (or, failing that, within a word).
+fn segments(text: &str, max_chars: usize) -> Vec<Range<usize>> {
+    let trimmed = trim_range(text, 0..text.len());
+    let mut sentences = vec![];
+    let mut start = trimmed.start;
+    for end in SENTENCE_END.find_iter(&text[..trimmed.end]) {
+        if end.start() >= start {
+            sentences.push(trim_range(text, start..end.end()));
+            start = end.end();
+        }
+    }
+    sentences.push(trim_range(text, start..trimmed.end));
+    sentences
+        .into_iter()
+        .filter(|sentence| !sentence.is_empty())
+        .flat_map(|sentence| split_sentence(text, sentence, max_chars))
+        .collect()
+}
+
+/// Pack consecutive sentences of the text into chunks of at most `max_chars` characters, with as
+/// many sentences as possible in each chunk.
+fn pack(text: &str, sentences: &[Range<usize>], max_chars: usize) -> Vec<Vec<Range<usize>>> {
+    let mut chunks: Vec<Vec<Range<usize>>> = vec![];
+    for sentence in sentences {
+        match chunks.last_mut() {
+            Some(chunk) if text[chunk[0].start..sentence.end].chars().count() <= max_chars => {
+                chunk.push(sentence.clone());
+            }
+            _ => chunks.push(vec![sentence.clone()]),
+        }
+    }
+    chunks
+}
+
+/// The range of the text from the first to the last of some consecutive sentences.
+fn span(sentences: &[Range<usize>]) -> Range<usize> {
+    sentences[0].start..sentences[sentences.len() - 1].end
+}
+
+/// Split a sentence that may be too long into chunks, between words where possible.
+fn split_sentence(text: &str, sentence: Range<usize>, max_chars: usize) -> Vec<Range<usize>> {
+    let mut chunks = vec![];
+    let mut start = sentence.start;
+    while text[start..sentence.end].chars().count() > max_chars {
+        // The byte offset just after the maximum number of characters.
+        let limit = text[start..]
+            .char_indices()
+            .nth(max_chars)
+            .map_or(sentence.end, |(i, _)| start + i);
+        let end = text[start..limit]
+            .rfind(char::is_whitespace)
+            .map(|i| start + i)
+            .filter(|end| *end > start)
+            .unwrap_or(limit);
+        chunks.push(trim_range(text, start..end));
+        start = trim_range(text, end..sentence.end).start;
+    }
+    chunks.push(start..sentence.end);
+    chunks
+}
+
+/// Remove the whitespace from the start and end of a range of the text.
+fn trim_range(text: &str, range: Range<usize>) -> Range<usize> {
+    let slice = &text[range.clone()];
+    let start = range.start + (slice.len() - slice.trim_start().len());
+    let end = range.end - (slice.len() - slice.trim_end().len());
+    start..end.max(start)
+}
@@ -0,0 +1,167 @@
+use crate::cache::TtlCache;
+use crate::chunking::{pack, segments, span, ChunkingConfig, ChunkingTranslationService};
+use crate::services::translation::{TranslationError, TranslationService};
+use futures::future::{ready, BoxFuture};
+use futures::FutureExt;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Translates text to upper case, recording the text of every request. Text containing "fail"
+/// cannot be translated and, in text containing "run on", the sentences are run together.
+#[derive(Default)]
+struct RecordingTranslationService {
+    requests: Mutex<Vec<String>>,
+}
+
+impl TranslationService for RecordingTranslationService {
+    fn attempt_translation<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<String, TranslationError>> {
+        self.requests.lock().unwrap().push(text.to_string());
+        if text.contains("fail") {
+            ready(Err(TranslationError::TranslationFailed)).boxed()
+        } else if text.contains("run on") {
+            ready(Ok(text.to_uppercase().replace(". ", ", "))).boxed()
+        } else {
+            ready(Ok(text.to_uppercase())).boxed()
+        }
+    }
+}
+
+fn make_service(max_chars: usize) -> ChunkingTranslationService<RecordingTranslationService> {
+    ChunkingTranslationService::new(
+        RecordingTranslationService::default(),
+        ChunkingConfig {
+            max_chars: NonZeroUsize::new(max_chars).unwrap(),
+            concurrency: NonZeroUsize::new(2).unwrap(),
+        },
+        TtlCache::new(NonZeroUsize::new(16).unwrap(), Duration::from_secs(60)),
+    )
+}
+
+fn chunks(text: &str, max_chars: usize) -> Vec<&str> {
+    pack(text, &segments(text, max_chars), max_chars)
+        .iter()
+        .map(|sentences| &text[span(sentences)])
+        .collect()
+}
+
+#[test]
+fn short_text_not_split() {
+    assert_eq!(chunks(" One. Two. ", 20), vec!["One. Two."]);
+    assert!(chunks("  ", 20).is_empty());
+}
+
+#[test]
+fn split_at_sentences() {
+    let text = "It is small. It is yellow!  It \"sparks\"? It is electric.";
+    assert_eq!(
+        chunks(text, 30),
+        vec![
+            "It is small. It is yellow!",
+            "It \"sparks\"? It is electric."
+        ]
+    );
+    assert_eq!(
+        chunks(text, 12),
+        vec![
+            "It is small.",
+            "It is",
+            "yellow!",
+            "It \"sparks\"?",
+            "It is",
+            "electric."
+        ]
+    );
+}
+
+#[test]
+fn long_words_split() {
+    assert_eq!(chunks("Pokémon", 3), vec!["Pok", "émo", "n"]);
+}
+
+#[tokio::test]
+async fn spacing_preserved() {
+    let service = make_service(14);
+    let text = " It is small.\nIt is  yellow. ";
+    assert_eq!(
+        service.attempt_translation(text).await,
+        Ok(" IT IS SMALL.\nIT IS  YELLOW. ".to_string())
+    );
+    assert_eq!(
+        *service.inner.requests.lock().unwrap(),
+        vec!["It is small.", "It is  yellow."]
+    );
+}
+
+#[tokio::test]
+async fn chunks_cached() {
+    let service = make_service(15);
+    assert!(service
+        .attempt_translation("It is small. It is yellow.")
+        .await
+        .is_ok());
+    assert_eq!(
+        service
+            .attempt_translation("It is large. It is yellow.")
+            .await,
+        Ok("IT IS LARGE. IT IS YELLOW.".to_string())
+    );
+    assert_eq!(
+        *service.inner.requests.lock().unwrap(),
+        vec!["It is small.", "It is yellow.", "It is large."]
+    );
+}
+
+#[tokio::test]
+async fn sentences_cached_within_chunks() {
+    let service = make_service(400);
+    assert_eq!(
+        service
+            .attempt_translation("It is small. It is yellow.")
+            .await,
+        Ok("IT IS SMALL. IT IS YELLOW.".to_string())
+    );
+    assert_eq!(
+        service
+            .attempt_translation("It is large.  It is yellow. It is fast.")
+            .await,
+        Ok("IT IS LARGE.  IT IS YELLOW. IT IS FAST.".to_string())
+    );
+    // Only the sentences that have not been translated are sent, each run of them as one chunk.
+    assert_eq!(
+        *service.inner.requests.lock().unwrap(),
+        vec!["It is small. It is yellow.", "It is large.", "It is fast."]
+    );
+}
+
+#[tokio::test]
+async fn mismatched_sentences_not_cached() {
+    let service = make_service(400);
+    assert_eq!(
+        service.attempt_translation("It is a run on. Yes.").await,
+        Ok("IT IS A RUN ON, YES.".to_string())
+    );
+    // The translation cannot be attributed to the individual sentences.
+    assert_eq!(
+        service.attempt_translation("Yes.").await,
+        Ok("YES.".to_string())
+    );
+    assert_eq!(service.inner.requests.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn failed_chunk_fails_translation() {
+    let service = make_service(15);
+    assert_eq!(
+        service
+            .attempt_translation("It is small. It will fail.")
+            .await,
+        Err(TranslationError::TranslationFailed)
+    );
+    // The successful chunk is still cached.
+    assert!(service.attempt_translation("It is small.").await.is_ok());
+    assert_eq!(service.inner.requests.lock().unwrap().len(), 2);
+}
@@ -0,0 +1,312 @@
+use crate::model::{PokemonData, PokemonDescriptions};
+use crate::services::pokemon::{
+    DescriptionPreferences, DescriptionsService, PokemonService, PokemonServiceError,
+};
+use crate::services::translation::{TranslationError, TranslationService};
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{event, Level};
+
+#[cfg(test)]
+mod tests;
+
+/// The state of a circuit breaker.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CircuitState {
+    /// Requests are passed to the service.
+    Closed,
+    /// The service has failed repeatedly and requests will fail immediately.
+    Open,
+    /// The cool-down period has expired and a single request will be allowed through to probe
+    /// whether the service has recovered.
+    HalfOpen,
+}
+
+/// Configuration for a circuit breaker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BreakerConfig {
+    /// The number of consecutive failures after which the circuit will open.
+    pub failure_threshold: u32,
+    /// How long the circuit will remain open before allowing a probe request.
+    pub cool_down: Duration,
+}
+
+/// A snapshot of the status of a circuit, for reporting.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CircuitStatus {
+    pub name: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+}
+
+/// The shared state of a circuit breaker.
+pub struct Circuit {
+    name: String,
+    config: BreakerConfig,
+    inner: Mutex<CircuitInner>,
+}
+
+struct CircuitInner {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    probe_in_flight: bool,
+}
+
+/// The outcome of a request, as far as the circuit is concerned.
+enum Outcome {
+    Success,
+    Failure,
+    /// The request gave no information about the health of the service.
+    Ignored,
+}
+
+impl Circuit {
+    fn new(name: &str, config: BreakerConfig) -> Self {
+        Circuit {
+            name: name.to_string(),
+            config,
+            inner: Mutex::new(CircuitInner {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                probe_in_flight: false,
+            }),
+        }
+    }
+
+    /// Get the current status of the circuit.
+    pub fn status(&self) -> CircuitStatus {
+        let inner = self.inner.lock().unwrap();
+        CircuitStatus {
+            name: self.name.clone(),
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+        }
+    }
+
+    /// Attempt to get permission to make a request.
+    fn try_acquire(&self, now: Instant) -> Option<Permit<'_>> {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.state {
+            CircuitState::Closed => Some(Permit::new(self, false)),
+            CircuitState::Open => {
+                let cooled_down = inner
+                    .opened_at
+                    .map(|opened| now.duration_since(opened) >= self.config.cool_down)
+                    .unwrap_or(true);
+                if cooled_down {
+                    self.transition(&mut inner, CircuitState::HalfOpen);
+                    inner.probe_in_flight = true;
+                    Some(Permit::new(self, true))
+                } else {
+                    None
+                }
+            }
+            CircuitState::HalfOpen if !inner.probe_in_flight => {
+                inner.probe_in_flight = true;
+                Some(Permit::new(self, true))
+            }
+            CircuitState::HalfOpen => None,
+        }
+    }
+
+    fn record(&self, outcome: Outcome, probe: bool, now: Instant) {
+        let mut inner = self.inner.lock().unwrap();
+        if probe {
+            inner.probe_in_flight = false;
+        }
+        match outcome {
+            Outcome::Success => {
+                inner.consecutive_failures = 0;
+                if inner.state != CircuitState::Closed {
+                    self.transition(&mut inner, CircuitState::Closed);
+                    inner.opened_at = None;
+                }
+            }
+            Outcome::Failure => {
+                inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+                let trip = match inner.state {
+                    CircuitState::Closed => {
+                        inner.consecutive_failures >= self.config.failure_threshold
+                    }
+                    CircuitState::HalfOpen => probe,
+                    CircuitState::Open => false,
+                };
+                if trip {
+                    self.transition(&mut inner, CircuitState::Open);
+                    inner.opened_at = Some(now);
+                }
+            }
+            Outcome::Ignored => {}
+        }
+    }
+
+    fn transition(&self, inner: &mut CircuitInner, to: CircuitState) {
+        let from = inner.state;
+        inner.state = to;
+        let circuit = self.name.as_str();
+        match to {
+            CircuitState::Open => {
+                event!(Level::WARN, message = "Circuit opened.", %circuit, ?from, failures = inner.consecutive_failures);
+            }
+            CircuitState::HalfOpen => {
+                event!(Level::INFO, message = "Circuit half-open. Probing for recovery.", %circuit, ?from);
+            }
+            CircuitState::Closed => {
+                event!(Level::INFO, message = "Circuit closed.", %circuit, ?from);
+            }
+        }
+    }
+}
+
+/// Permission to make a single request through the circuit. If the request is abandoned before an
+/// outcome is recorded, a probe will be released so that another request can take its place.
+struct Permit<'a> {
+    circuit: &'a Circuit,
+    probe: bool,
+    recorded: bool,
+}
+
+impl<'a> Permit<'a> {
+    fn new(circuit: &'a Circuit, probe: bool) -> Self {
+        Permit {
+            circuit,
+            probe,
+            recorded: false,
+        }
+    }
+
+    fn record(mut self, outcome: Outcome, now: Instant) {
+        self.recorded = true;
+        self.circuit.record(outcome, self.probe, now);
+    }
+}
+
+impl<'a> Drop for Permit<'a> {
+    fn drop(&mut self) {
+        if !self.recorded && self.probe {
+            self.circuit.inner.lock().unwrap().probe_in_flight = false;
+        }
+    }
+}
+
+/// Wraps a service with a circuit breaker. After a number of consecutive failures, the circuit
+/// opens and requests fail immediately, without calling the service. After a cool-down period, a
+/// single request is allowed through to probe whether the service has recovered.
+pub struct CircuitBreaker<S> {
+    inner: S,
+    circuit: Arc<Circuit>,
+}
+
+impl<S> CircuitBreaker<S> {
+    /// # Arguments
+    /// * `inner` - The service to protect.
+    /// * `name` - Name of the circuit (for logging and reporting).
+    /// * `config` - Configuration for the circuit.
+    pub fn new(inner: S, name: &str, config: BreakerConfig) -> Self {
+        CircuitBreaker {
+            inner,
+            circuit: Arc::new(Circuit::new(name, config)),
+        }
+    }
+
+    /// A handle to the state of the circuit.
+    pub fn circuit(&self) -> Arc<Circuit> {
+        self.circuit.clone()
+    }
+}
+
+impl<S> PokemonService for CircuitBreaker<S>
+where
+    S: PokemonService + Send + Sync,
+{
+    fn get_pokemon<'a>(
+        &'a self,
+        name: &'a str,
+        preferences: &'a DescriptionPreferences,
+    ) -> BoxFuture<'a, Result<PokemonData, PokemonServiceError>> {
+        async move {
+            let CircuitBreaker { inner, circuit } = self;
+            let permit = if let Some(permit) = circuit.try_acquire(Instant::now()) {
+                permit
+            } else {
+                event!(Level::DEBUG, message = "Circuit is open. Failing fast.", circuit = %circuit.name);
+                return Err(PokemonServiceError::ServiceUnavailable);
+            };
+            let result = inner.get_pokemon(name, preferences).await;
+            permit.record(pokemon_outcome(&result), Instant::now());
+            result
+        }
+        .boxed()
+    }
+}
+
+impl<S> DescriptionsService for CircuitBreaker<S>
+where
+    S: DescriptionsService + Send + Sync,
+{
+    fn get_descriptions<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<PokemonDescriptions, PokemonServiceError>> {
+        async move {
+            let CircuitBreaker { inner, circuit } = self;
+            let permit = if let Some(permit) = circuit.try_acquire(Instant::now()) {
+                permit
+            } else {
+                event!(Level::DEBUG, message = "Circuit is open. Failing fast.", circuit = %circuit.name);
+                return Err(PokemonServiceError::ServiceUnavailable);
+            };
+            let result = inner.get_descriptions(name).await;
+            permit.record(pokemon_outcome(&result), Instant::now());
+            result
+        }
+        .boxed()
+    }
+}
+
+/// Only failures of the Pokemon service itself count against the circuit (a Pokemon that does not
+/// exist is a successful response).
+fn pokemon_outcome<T>(result: &Result<T, PokemonServiceError>) -> Outcome {
+    match result {
+        Err(PokemonServiceError::ServiceUnavailable | PokemonServiceError::Timeout) => {
+            Outcome::Failure
+        }
+        _ => Outcome::Success,
+    }
+}
+
+impl<S> TranslationService for CircuitBreaker<S>
+where
+    S: TranslationService + Send + Sync,
+{
+    fn attempt_translation<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<String, TranslationError>> {
+        async move {
+            let CircuitBreaker { inner, circuit } = self;
+            let permit = if let Some(permit) = circuit.try_acquire(Instant::now()) {
+                permit
+            } else {
+                event!(Level::DEBUG, message = "Circuit is open. Failing fast.", circuit = %circuit.name);
+                return Err(TranslationError::ServiceUnavailable);
+            };
+            let result = inner.attempt_translation(text).await;
+            permit.record(match &result {
+                Err(TranslationError::ServiceUnavailable | TranslationError::Timeout) => {
+                    Outcome::Failure
+                }
+                Err(TranslationError::RateLimited(_)) => Outcome::Ignored,
+                _ => Outcome::Success,
+            }, Instant::now());
+            result
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,136 @@
+use crate::circuit_breaker::{BreakerConfig, Circuit, CircuitBreaker, CircuitState, Outcome};
+use crate::services::translation::{TranslationError, TranslationService};
+use futures::future::{ready, BoxFuture};
+use futures::FutureExt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const CONFIG: BreakerConfig = BreakerConfig {
+    failure_threshold: 2,
+    cool_down: Duration::from_secs(10),
+};
+
+fn fail(circuit: &Circuit, now: Instant) {
+    circuit
+        .try_acquire(now)
+        .unwrap()
+        .record(Outcome::Failure, now);
+}
+
+#[test]
+fn open_after_threshold() {
+    let circuit = Circuit::new("test", CONFIG);
+    let now = Instant::now();
+
+    fail(&circuit, now);
+    assert_eq!(circuit.status().state, CircuitState::Closed);
+    fail(&circuit, now);
+    assert_eq!(circuit.status().state, CircuitState::Open);
+    assert_eq!(circuit.status().consecutive_failures, 2);
+
+    assert!(circuit.try_acquire(now + Duration::from_secs(1)).is_none());
+}
+
+#[test]
+fn success_resets_failures() {
+    let circuit = Circuit::new("test", CONFIG);
+    let now = Instant::now();
+
+    fail(&circuit, now);
+    circuit
+        .try_acquire(now)
+        .unwrap()
+        .record(Outcome::Success, now);
+    fail(&circuit, now);
+
+    assert_eq!(circuit.status().state, CircuitState::Closed);
+}
+
+#[test]
+fn probe_after_cool_down() {
+    let circuit = Circuit::new("test", CONFIG);
+    let now = Instant::now();
+    fail(&circuit, now);
+    fail(&circuit, now);
+
+    let later = now + CONFIG.cool_down;
+    let probe = circuit.try_acquire(later).unwrap();
+    assert_eq!(circuit.status().state, CircuitState::HalfOpen);
+
+    // Only a single probe is permitted at a time.
+    assert!(circuit.try_acquire(later).is_none());
+
+    probe.record(Outcome::Success, later);
+    assert_eq!(circuit.status().state, CircuitState::Closed);
+    assert!(circuit.try_acquire(later).is_some());
+}
+
+#[test]
+fn failed_probe_reopens() {
+    let circuit = Circuit::new("test", CONFIG);
+    let now = Instant::now();
+    fail(&circuit, now);
+    fail(&circuit, now);
+
+    let later = now + CONFIG.cool_down;
+    fail(&circuit, later);
+
+    assert_eq!(circuit.status().state, CircuitState::Open);
+    assert!(circuit
+        .try_acquire(later + Duration::from_secs(1))
+        .is_none());
+    assert!(circuit.try_acquire(later + CONFIG.cool_down).is_some());
+}
+
+#[test]
+fn abandoned_probe_released() {
+    let circuit = Circuit::new("test", CONFIG);
+    let now = Instant::now();
+    fail(&circuit, now);
+    fail(&circuit, now);
+
+    let later = now + CONFIG.cool_down;
+    drop(circuit.try_acquire(later).unwrap());
+
+    assert!(circuit.try_acquire(later).is_some());
+}
+
+/// Fake translation service that can be switched between failing and succeeding.
+#[derive(Default)]
+struct FlakyTranslator {
+    failing: AtomicBool,
+    calls: AtomicUsize,
+}
+
+impl TranslationService for Arc<FlakyTranslator> {
+    fn attempt_translation<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<String, TranslationError>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        ready(if self.failing.load(Ordering::SeqCst) {
+            Err(TranslationError::ServiceUnavailable)
+        } else {
+            Ok(text.to_uppercase())
+        })
+        .boxed()
+    }
+}
+
+#[tokio::test]
+async fn fail_fast_when_open() {
+    let translator = Arc::new(FlakyTranslator::default());
+    translator.failing.store(true, Ordering::SeqCst);
+    let breaker = CircuitBreaker::new(translator.clone(), "test", CONFIG);
+
+    for _ in 0..4 {
+        assert_eq!(
+            breaker.attempt_translation("text").await,
+            Err(TranslationError::ServiceUnavailable)
+        );
+    }
+
+    assert_eq!(translator.calls.load(Ordering::SeqCst), 2);
+    assert_eq!(breaker.circuit().status().state, CircuitState::Open);
+}
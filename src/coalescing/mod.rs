@@ -0,0 +1,215 @@
+use crate::model::{PokemonData, PokemonDescriptions};
+use crate::names::to_slug;
+use crate::services::pokemon::{
+    DescriptionPreferences, DescriptionsService, PokemonService, PokemonServiceError,
+};
+use crate::services::translation::{TranslationError, TranslationService};
+use futures::future::{BoxFuture, Shared, WeakShared};
+use futures::FutureExt;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::{event, Level};
+
+#[cfg(test)]
+mod tests;
+
+type InFlight<T> = Mutex<HashMap<String, WeakShared<BoxFuture<'static, T>>>>;
+
+/// Tracks the requests that are currently in flight so that concurrent requests with the same key
+/// can share a single future. Only the callers waiting for a request keep it alive, so a request
+/// that every caller has abandoned (for example, when the deadline expires) is dropped rather than
+/// joined by later callers.
+struct SingleFlight<T> {
+    in_flight: InFlight<T>,
+}
+
+impl<T> Default for SingleFlight<T> {
+    fn default() -> Self {
+        SingleFlight {
+            in_flight: Default::default(),
+        }
+    }
+}
+
+impl<T: Clone> SingleFlight<T> {
+    /// Wait for the result of the request for a key. If no request for the key is in flight, a new
+    /// one will be started with `start`.
+    async fn run<F>(&self, key: String, start: F) -> T
+    where
+        F: FnOnce() -> BoxFuture<'static, T>,
+    {
+        let request = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            if let Some(request) = in_flight.get(&key).and_then(WeakShared::upgrade) {
+                event!(Level::DEBUG, message = "Joining request already in flight.", %key);
+                request
+            } else {
+                let request = start().shared();
+                if let Some(weak) = request.downgrade() {
+                    in_flight.insert(key.clone(), weak);
+                }
+                request
+            }
+        };
+
+        let mut waiter = Waiter {
+            in_flight: &self.in_flight,
+            key,
+            request: Some(request.clone()),
+            complete: false,
+        };
+        let result = request.await;
+        waiter.complete = true;
+        result
+    }
+}
+
+/// A caller waiting for a request in flight. When the request completes, or the last caller
+/// waiting for it goes away, the request is no longer in flight.
+struct Waiter<'a, T> {
+    in_flight: &'a InFlight<T>,
+    key: String,
+    request: Option<Shared<BoxFuture<'static, T>>>,
+    complete: bool,
+}
+
+impl<'a, T> Drop for Waiter<'a, T> {
+    fn drop(&mut self) {
+        let mut in_flight = match self.in_flight.lock() {
+            Ok(in_flight) => in_flight,
+            Err(_) => return,
+        };
+        let request = self.request.take();
+        let finished = if self.complete {
+            match in_flight.get(&self.key).map(WeakShared::upgrade) {
+                // Another caller may already have started a new request for the key.
+                Some(Some(current)) => request.is_some_and(|request| request.ptr_eq(&current)),
+                Some(None) => true,
+                None => false,
+            }
+        } else {
+            // The request is abandoned once every caller waiting for it has gone away.
+            drop(request);
+            in_flight
+                .get(&self.key)
+                .is_some_and(|current| current.upgrade().is_none())
+        };
+        if finished {
+            event!(Level::DEBUG, message = "Request no longer in flight.", key = %self.key, complete = self.complete);
+            in_flight.remove(&self.key);
+        }
+    }
+}
+
+/// Wraps a Pokemon service so that concurrent requests for the same Pokemon result in a single
+/// request to the underlying service, the result of which is shared between all of the callers.
+pub struct CoalescingPokemonService<S> {
+    inner: Arc<S>,
+    in_flight: SingleFlight<Result<PokemonData, PokemonServiceError>>,
+    descriptions_in_flight: SingleFlight<Result<PokemonDescriptions, PokemonServiceError>>,
+}
+
+impl<S> CoalescingPokemonService<S> {
+    /// # Arguments
+    /// * `inner` - The underlying Pokemon service.
+    pub fn new(inner: S) -> Self {
+        CoalescingPokemonService {
+            inner: Arc::new(inner),
+            in_flight: Default::default(),
+            descriptions_in_flight: Default::default(),
+        }
+    }
+}
+
+impl<S> PokemonService for CoalescingPokemonService<S>
+where
+    S: PokemonService + Send + Sync + 'static,
+{
+    fn get_pokemon<'a>(
+        &'a self,
+        name: &'a str,
+        preferences: &'a DescriptionPreferences,
+    ) -> BoxFuture<'a, Result<PokemonData, PokemonServiceError>> {
+        let CoalescingPokemonService {
+            inner, in_flight, ..
+        } = self;
+        in_flight
+            .run(
+                format!("{}?{}", normalise_name(name), preferences),
+                move || {
+                    let inner = inner.clone();
+                    let name = name.to_string();
+                    let preferences = preferences.clone();
+                    async move { inner.get_pokemon(name.as_str(), &preferences).await }.boxed()
+                },
+            )
+            .boxed()
+    }
+}
+
+impl<S> DescriptionsService for CoalescingPokemonService<S>
+where
+    S: DescriptionsService + Send + Sync + 'static,
+{
+    fn get_descriptions<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<PokemonDescriptions, PokemonServiceError>> {
+        let CoalescingPokemonService {
+            inner,
+            descriptions_in_flight,
+            ..
+        } = self;
+        descriptions_in_flight
+            .run(normalise_name(name), move || {
+                let inner = inner.clone();
+                let name = name.to_string();
+                async move { inner.get_descriptions(name.as_str()).await }.boxed()
+            })
+            .boxed()
+    }
+}
+
+/// Wraps a translation service so that concurrent requests to translate the same text result in a
+/// single request to the underlying service, the result of which is shared between all of the
+/// callers.
+pub struct CoalescingTranslationService<S> {
+    inner: Arc<S>,
+    in_flight: SingleFlight<Result<String, TranslationError>>,
+}
+
+impl<S> CoalescingTranslationService<S> {
+    /// # Arguments
+    /// * `inner` - The underlying translation service.
+    pub fn new(inner: S) -> Self {
+        CoalescingTranslationService {
+            inner: Arc::new(inner),
+            in_flight: Default::default(),
+        }
+    }
+}
+
+impl<S> TranslationService for CoalescingTranslationService<S>
+where
+    S: TranslationService + Send + Sync + 'static,
+{
+    fn attempt_translation<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<String, TranslationError>> {
+        let CoalescingTranslationService { inner, in_flight } = self;
+        in_flight
+            .run(text.to_string(), move || {
+                let inner = inner.clone();
+                let text = text.to_string();
+                async move { inner.attempt_translation(text.as_str()).await }.boxed()
+            })
+            .boxed()
+    }
+}
+
+/// Requests for different forms of the same name (for example "Mr. Mime" and "mr-mime") are
+/// considered to be the same.
+fn normalise_name(name: &str) -> String {
+    to_slug(name)
+}
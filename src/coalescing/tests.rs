@@ -0,0 +1,186 @@
+use crate::coalescing::{CoalescingPokemonService, CoalescingTranslationService};
+use crate::model::PokemonData;
+use crate::services::pokemon::{DescriptionPreferences, PokemonService, PokemonServiceError};
+use crate::services::translation::{TranslationError, TranslationService};
+use futures::channel::oneshot;
+use futures::future::{pending, ready, BoxFuture, Shared};
+use futures::FutureExt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Fake service that counts the calls made to it and does not complete any request until its gate
+/// is opened.
+#[derive(Clone)]
+struct GatedService {
+    calls: Arc<AtomicUsize>,
+    gate: Shared<oneshot::Receiver<()>>,
+}
+
+impl GatedService {
+    fn new() -> (Self, oneshot::Sender<()>) {
+        let (tx, rx) = oneshot::channel();
+        let service = GatedService {
+            calls: Default::default(),
+            gate: rx.shared(),
+        };
+        (service, tx)
+    }
+
+    fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+impl PokemonService for GatedService {
+    fn get_pokemon<'a>(
+        &'a self,
+        name: &'a str,
+        _preferences: &'a DescriptionPreferences,
+    ) -> BoxFuture<'a, Result<PokemonData, PokemonServiceError>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let gate = self.gate.clone();
+        async move {
+            let _ = gate.await;
+            if name.to_lowercase() == "name" {
+                Ok(PokemonData::new(
+                    "name".to_string(),
+                    "A description.".to_string(),
+                ))
+            } else {
+                Err(PokemonServiceError::no_such_pokemon(name))
+            }
+        }
+        .boxed()
+    }
+}
+
+impl TranslationService for GatedService {
+    fn attempt_translation<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<String, TranslationError>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let gate = self.gate.clone();
+        async move {
+            let _ = gate.await;
+            Ok(text.to_uppercase())
+        }
+        .boxed()
+    }
+}
+
+#[tokio::test]
+async fn coalesce_pokemon_requests() {
+    let (inner, gate) = GatedService::new();
+    let service = CoalescingPokemonService::new(inner.clone());
+    let preferences = DescriptionPreferences::default();
+    // Requests with different preferences may have different results.
+    let other_preferences = DescriptionPreferences {
+        versions: vec!["red".to_string()],
+        languages: vec![],
+    };
+
+    let (first, second, third, fourth, _) = futures::join!(
+        service.get_pokemon("name", &preferences),
+        service.get_pokemon("Name", &preferences),
+        service.get_pokemon("other", &preferences),
+        service.get_pokemon("name", &other_preferences),
+        async move { gate.send(()).unwrap() }
+    );
+
+    let expected = PokemonData::new("name".to_string(), "A description.".to_string());
+    assert_eq!(first, Ok(expected.clone()));
+    assert_eq!(second, Ok(expected.clone()));
+    assert_eq!(third, Err(PokemonServiceError::no_such_pokemon("other")));
+    assert_eq!(fourth, Ok(expected.clone()));
+    assert_eq!(inner.calls(), 3);
+
+    // Once complete, subsequent requests are not coalesced.
+    assert_eq!(
+        service.get_pokemon("name", &preferences).await,
+        Ok(expected)
+    );
+    assert_eq!(inner.calls(), 4);
+}
+
+#[tokio::test]
+async fn coalesce_translation_requests() {
+    let (inner, gate) = GatedService::new();
+    let service = CoalescingTranslationService::new(inner.clone());
+
+    let (first, second, third, _) = futures::join!(
+        service.attempt_translation("text"),
+        service.attempt_translation("text"),
+        service.attempt_translation("Text"),
+        async move { gate.send(()).unwrap() }
+    );
+
+    assert_eq!(first, Ok("TEXT".to_string()));
+    assert_eq!(second, Ok("TEXT".to_string()));
+    assert_eq!(third, Ok("TEXT".to_string()));
+    assert_eq!(inner.calls(), 2);
+}
+
+/// Fake translator that never completes the first request, but completes any later ones.
+#[derive(Default, Clone)]
+struct HangsOnceService {
+    calls: Arc<AtomicUsize>,
+}
+
+impl TranslationService for HangsOnceService {
+    fn attempt_translation<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<String, TranslationError>> {
+        if self.calls.fetch_add(1, Ordering::SeqCst) == 0 {
+            pending().boxed()
+        } else {
+            ready(Ok(text.to_uppercase())).boxed()
+        }
+    }
+}
+
+#[tokio::test]
+async fn abandoned_request_not_joined() {
+    let inner = HangsOnceService::default();
+    let service = CoalescingTranslationService::new(inner.clone());
+
+    let cancelled = tokio::time::timeout(
+        Duration::from_millis(10),
+        service.attempt_translation("text"),
+    )
+    .await;
+    assert!(cancelled.is_err());
+    assert!(service.in_flight.in_flight.lock().unwrap().is_empty());
+
+    // A later request is made to the (recovered) service rather than joining the abandoned one.
+    assert_eq!(
+        service.attempt_translation("text").await,
+        Ok("TEXT".to_string())
+    );
+    assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn request_kept_for_remaining_callers() {
+    let (inner, gate) = GatedService::new();
+    let service = CoalescingTranslationService::new(inner.clone());
+
+    let (cancelled, waiting, _) = futures::join!(
+        tokio::time::timeout(
+            Duration::from_millis(10),
+            service.attempt_translation("text")
+        ),
+        service.attempt_translation("text"),
+        async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            gate.send(()).unwrap()
+        }
+    );
+
+    assert!(cancelled.is_err());
+    assert_eq!(waiting, Ok("TEXT".to_string()));
+    assert_eq!(inner.calls(), 1);
+    assert!(service.in_flight.in_flight.lock().unwrap().is_empty());
+}
@@ -0,0 +1,233 @@
+use crate::config::Params;
+use serde::Deserialize;
+use std::fmt::{Debug, Formatter};
+use std::path::Path;
+
+/// Models the configuration file. Every value is optional as any of them may be provided on the
+/// command line instead.
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileConfig {
+    pub server: ServerSection,
+    pub pokemon: PokemonSection,
+    pub shakespeare: ShakespeareSection,
+    pub cache: CacheSection,
+    pub retry: RetrySection,
+    pub breaker: BreakerSection,
+    pub logging: LoggingSection,
+    pub tls: TlsSection,
+    pub suggestions: SuggestionsSection,
+}
+
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSection {
+    pub bind: Option<String>,
+    pub port: Option<u16>,
+    pub deadline: Option<u64>,
+    pub fallback: Option<bool>,
+    pub batch_concurrency: Option<usize>,
+}
+
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct PokemonSection {
+    pub url: Option<String>,
+    pub connect_timeout: Option<u64>,
+    pub timeout: Option<u64>,
+    pub versions: Option<Vec<String>>,
+    pub languages: Option<Vec<String>>,
+    pub normalise_casing: Option<bool>,
+}
+
+#[derive(Deserialize, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShakespeareSection {
+    pub url: Option<String>,
+    pub connect_timeout: Option<u64>,
+    pub timeout: Option<u64>,
+    pub api_key: Option<String>,
+    pub api_key_file: Option<String>,
+    pub styles: Option<Vec<String>>,
+    pub offline: Option<String>,
+    pub chain: Option<Vec<String>>,
+    pub chunk_size: Option<usize>,
+    pub concurrency: Option<usize>,
+}
+
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheSection {
+    pub ttl: Option<u64>,
+    pub capacity: Option<usize>,
+    pub translation_store: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetrySection {
+    pub attempts: Option<u32>,
+    pub delay: Option<u64>,
+    pub jitter: Option<f64>,
+    pub statuses: Option<Vec<u16>>,
+}
+
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BreakerSection {
+    pub threshold: Option<u32>,
+    pub cool_down: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingSection {
+    pub level: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsSection {
+    pub cert: Option<String>,
+    pub key: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SuggestionsSection {
+    pub refresh: Option<u64>,
+    pub auto_resolve: Option<bool>,
+}
+
+impl FileConfig {
+    /// Load the configuration from a file. The format is determined by the extension of the file
+    /// (".toml", ".yaml" or ".yml").
+    pub fn load(path: &Path) -> Result<FileConfig, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            format!(
+                "Failed to read the configuration file {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_lowercase);
+        let parsed = match extension.as_deref() {
+            Some("toml") => FileConfig::from_toml(contents.as_str()),
+            Some("yaml") | Some("yml") => FileConfig::from_yaml(contents.as_str()),
+            _ => {
+                return Err(format!(
+                    "The format of the configuration file {} could not be determined (expected a .toml, .yaml or .yml file).",
+                    path.display()
+                ))
+            }
+        };
+        parsed.map_err(|e| {
+            format!(
+                "{} is not a valid configuration file: {}",
+                path.display(),
+                e
+            )
+        })
+    }
+
+    pub fn from_toml(contents: &str) -> Result<FileConfig, String> {
+        toml::from_str(contents).map_err(|e| e.to_string())
+    }
+
+    pub fn from_yaml(contents: &str) -> Result<FileConfig, String> {
+        serde_yaml::from_str(contents).map_err(|e| e.to_string())
+    }
+}
+
+/// The API key is redacted from debug output.
+impl Debug for ShakespeareSection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let ShakespeareSection {
+            url,
+            connect_timeout,
+            timeout,
+            api_key,
+            api_key_file,
+            styles,
+            offline,
+            chain,
+            chunk_size,
+            concurrency,
+        } = self;
+        f.debug_struct("ShakespeareSection")
+            .field("url", url)
+            .field("connect_timeout", connect_timeout)
+            .field("timeout", timeout)
+            .field("api_key", &api_key.as_ref().map(|_| "<redacted>"))
+            .field("api_key_file", api_key_file)
+            .field("styles", styles)
+            .field("offline", offline)
+            .field("chain", chain)
+            .field("chunk_size", chunk_size)
+            .field("concurrency", concurrency)
+            .finish()
+    }
+}
+
+impl From<FileConfig> for Params {
+    fn from(file: FileConfig) -> Self {
+        let FileConfig {
+            server,
+            pokemon,
+            shakespeare,
+            cache,
+            retry,
+            breaker,
+            logging,
+            tls,
+            suggestions,
+        } = file;
+        Params {
+            config: None,
+            bind: server.bind,
+            port: server.port,
+            pokemon: pokemon.url,
+            shakespeare: shakespeare.url,
+            cache_ttl: cache.ttl,
+            cache_capacity: cache.capacity,
+            translation_store: cache.translation_store,
+            fallback: server.fallback,
+            retry_attempts: retry.attempts,
+            retry_delay: retry.delay,
+            retry_jitter: retry.jitter,
+            retry_statuses: retry.statuses.map(|statuses| {
+                statuses
+                    .iter()
+                    .map(u16::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }),
+            breaker_threshold: breaker.threshold,
+            breaker_cool_down: breaker.cool_down,
+            pokemon_connect_timeout: pokemon.connect_timeout,
+            pokemon_timeout: pokemon.timeout,
+            shakespeare_connect_timeout: shakespeare.connect_timeout,
+            shakespeare_timeout: shakespeare.timeout,
+            deadline: server.deadline,
+            batch_concurrency: server.batch_concurrency,
+            log: logging.level,
+            tls_cert: tls.cert,
+            tls_key: tls.key,
+            shakespeare_api_key: shakespeare.api_key,
+            shakespeare_api_key_file: shakespeare.api_key_file,
+            species_refresh: suggestions.refresh,
+            auto_resolve: suggestions.auto_resolve,
+            versions: pokemon.versions.map(|versions| versions.join(",")),
+            languages: pokemon.languages.map(|languages| languages.join(",")),
+            normalise_casing: pokemon.normalise_casing,
+            styles: shakespeare.styles.map(|styles| styles.join(",")),
+            offline_translation: shakespeare.offline,
+            translation_chain: shakespeare.chain.map(|chain| chain.join(",")),
+            translation_chunk_size: shakespeare.chunk_size,
+            translation_concurrency: shakespeare.concurrency,
+        }
+    }
+}
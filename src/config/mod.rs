@@ -0,0 +1,760 @@
+use crate::chunking::ChunkingConfig;
+use crate::circuit_breaker::BreakerConfig;
+use crate::config::file::FileConfig;
+use crate::fun_translations::{ApiKey, SHAKESPEARE};
+use crate::retry::RetryPolicy;
+use crate::services::pokemon::DescriptionPreferences;
+use crate::tls::TlsConfig;
+use clap::Clap;
+use reqwest::{Client, StatusCode, Url};
+use std::collections::HashSet;
+use std::fmt::{Debug, Formatter};
+use std::net::{IpAddr, SocketAddr};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing_subscriber::EnvFilter;
+
+mod file;
+#[cfg(test)]
+mod tests;
+
+const DEFAULT_BIND: &str = "127.0.0.1";
+const DEFAULT_PORT: u16 = 8080;
+const DEFAULT_CACHE_TTL: u64 = 3600;
+const DEFAULT_CACHE_CAPACITY: usize = 1024;
+const DEFAULT_RETRY_ATTEMPTS: u32 = 3;
+const DEFAULT_RETRY_DELAY: u64 = 200;
+const DEFAULT_RETRY_JITTER: f64 = 0.5;
+const DEFAULT_RETRY_STATUSES: &str = "502,503,504";
+const DEFAULT_BREAKER_THRESHOLD: u32 = 5;
+const DEFAULT_BREAKER_COOL_DOWN: u64 = 30;
+const DEFAULT_CONNECT_TIMEOUT: u64 = 2000;
+const DEFAULT_TIMEOUT: u64 = 4000;
+const DEFAULT_DEADLINE: u64 = 30000;
+const DEFAULT_BATCH_CONCURRENCY: usize = 4;
+const DEFAULT_LOG: &str = "error";
+const DEFAULT_SPECIES_REFRESH: u64 = 86400;
+const DEFAULT_STYLES: &str = "shakespeare,yoda,pirate,minion";
+const DEFAULT_TRANSLATION_CHUNK_SIZE: usize = 400;
+const DEFAULT_TRANSLATION_CONCURRENCY: usize = 2;
+
+/// Parameters for the service. Each parameter can be provided on the command line or with an
+/// environment variable (prefixed with `POKESERVICE_`). Any parameter that is provided by neither
+/// will be taken from the configuration file (if there is one) or will take its default value.
+#[derive(Clap, Default, PartialEq)]
+#[clap(name = "pokemon-translator", version = "0.1")]
+pub struct Params {
+    /// Configuration file (TOML or YAML)
+    #[clap(short, long, env = "POKESERVICE_CONFIG")]
+    pub config: Option<String>,
+    /// Address to bind to [default: 127.0.0.1]
+    #[clap(short, long, env = "POKESERVICE_BIND")]
+    pub bind: Option<String>,
+    /// Port to bind to [default: 8080]
+    #[clap(short, long, env = "POKESERVICE_PORT")]
+    pub port: Option<u16>,
+    /// Base URL of the Pokemon API (i.e. https://pokeapi.co/api/v2/pokemon-species)
+    #[clap(short = 'a', long, env = "POKESERVICE_POKEMON")]
+    pub pokemon: Option<String>,
+    /// URL of the Shakespeare translation service (i.e. https://api.funtranslations.com/translate/shakespeare.json)
+    #[clap(short, long, env = "POKESERVICE_SHAKESPEARE")]
+    pub shakespeare: Option<String>,
+    /// Time (in seconds) for which translated responses are cached [default: 3600]
+    #[clap(long, env = "POKESERVICE_CACHE_TTL")]
+    pub cache_ttl: Option<u64>,
+    /// Maximum number of translated responses to cache [default: 1024]
+    #[clap(long, env = "POKESERVICE_CACHE_CAPACITY")]
+    pub cache_capacity: Option<usize>,
+    /// Path to a persistent store for translations (no translations are stored if absent)
+    #[clap(long, env = "POKESERVICE_TRANSLATION_STORE")]
+    pub translation_store: Option<String>,
+    /// Return the untranslated description if the translation service is unavailable (this can be
+    /// overridden with the "fallback" query parameter). A value may be given as "--fallback=false"
+    #[clap(
+        long,
+        env = "POKESERVICE_FALLBACK",
+        min_values = 0,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    pub fallback: Option<bool>,
+    /// Maximum number of attempts for each request to the remote services [default: 3]
+    #[clap(long, env = "POKESERVICE_RETRY_ATTEMPTS")]
+    pub retry_attempts: Option<u32>,
+    /// Delay (in milliseconds) before the first retry, doubling for each subsequent retry
+    /// [default: 200]
+    #[clap(long, env = "POKESERVICE_RETRY_DELAY")]
+    pub retry_delay: Option<u64>,
+    /// Proportion (between 0 and 1) of each retry delay that is randomized [default: 0.5]
+    #[clap(long, env = "POKESERVICE_RETRY_JITTER")]
+    pub retry_jitter: Option<f64>,
+    /// Comma separated list of the HTTP status codes for which requests will be retried
+    /// [default: 502,503,504]
+    #[clap(long, env = "POKESERVICE_RETRY_STATUSES")]
+    pub retry_statuses: Option<String>,
+    /// Number of consecutive failures of a remote service after which requests to it will fail
+    /// immediately [default: 5]
+    #[clap(long, env = "POKESERVICE_BREAKER_THRESHOLD")]
+    pub breaker_threshold: Option<u32>,
+    /// Time (in seconds) after which a request will be allowed to a failed remote service to check
+    /// whether it has recovered [default: 30]
+    #[clap(long, env = "POKESERVICE_BREAKER_COOL_DOWN")]
+    pub breaker_cool_down: Option<u64>,
+    /// Timeout (in milliseconds) for connecting to the Pokemon API [default: 2000]
+    #[clap(long, env = "POKESERVICE_POKEMON_CONNECT_TIMEOUT")]
+    pub pokemon_connect_timeout: Option<u64>,
+    /// Timeout (in milliseconds) for each request to the Pokemon API [default: 4000]
+    #[clap(long, env = "POKESERVICE_POKEMON_TIMEOUT")]
+    pub pokemon_timeout: Option<u64>,
+    /// Timeout (in milliseconds) for connecting to the Shakespeare translation service
+    /// [default: 2000]
+    #[clap(long, env = "POKESERVICE_SHAKESPEARE_CONNECT_TIMEOUT")]
+    pub shakespeare_connect_timeout: Option<u64>,
+    /// Timeout (in milliseconds) for each request to the Shakespeare translation service
+    /// [default: 4000]
+    #[clap(long, env = "POKESERVICE_SHAKESPEARE_TIMEOUT")]
+    pub shakespeare_timeout: Option<u64>,
+    /// Maximum time (in milliseconds) to spend handling each request to the service
+    /// [default: 30000]
+    #[clap(long, env = "POKESERVICE_DEADLINE")]
+    pub deadline: Option<u64>,
+    /// Maximum number of Pokemon looked up concurrently for a batch request [default: 4]
+    #[clap(long, env = "POKESERVICE_BATCH_CONCURRENCY")]
+    pub batch_concurrency: Option<usize>,
+    /// Logging filter (i.e. "info" or "pokeservice=debug"). The RUST_LOG environment variable
+    /// takes precedence over this [default: error]
+    #[clap(long, env = "POKESERVICE_LOG")]
+    pub log: Option<String>,
+    /// Certificate chain (PEM encoded) for serving HTTPS. The certificate is reloaded when the
+    /// service receives SIGHUP
+    #[clap(long, env = "POKESERVICE_TLS_CERT")]
+    pub tls_cert: Option<String>,
+    /// Private key (PEM encoded) for serving HTTPS
+    #[clap(long, env = "POKESERVICE_TLS_KEY")]
+    pub tls_key: Option<String>,
+    /// Secret for the paid tier of the Shakespeare translation service. Prefer
+    /// --shakespeare-api-key-file so that the secret is not visible in the process list
+    #[clap(long, env = "POKESERVICE_SHAKESPEARE_API_KEY", hide_env_values = true)]
+    pub shakespeare_api_key: Option<String>,
+    /// File containing the secret for the paid tier of the Shakespeare translation service
+    #[clap(long, env = "POKESERVICE_SHAKESPEARE_API_KEY_FILE")]
+    pub shakespeare_api_key_file: Option<String>,
+    /// Time (in seconds) between refreshes of the list of Pokemon species used to suggest
+    /// alternatives for unknown names [default: 86400]
+    #[clap(long, env = "POKESERVICE_SPECIES_REFRESH")]
+    pub species_refresh: Option<u64>,
+    /// Return the Pokemon for an unknown name if there is a single, close match for the name
+    #[clap(
+        long,
+        env = "POKESERVICE_AUTO_RESOLVE",
+        min_values = 0,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    pub auto_resolve: Option<bool>,
+    /// Comma separated list of the versions of the game from which descriptions are preferred, the
+    /// most preferred first (i.e. "sword,shield,ultra-sun"). If there is no description from any
+    /// of them, the description from the newest version is used (this can be overridden with the
+    /// "version" query parameter)
+    #[clap(long, env = "POKESERVICE_VERSIONS")]
+    pub versions: Option<String>,
+    /// Comma separated list of the languages of the descriptions (i.e. "de,fr"), the most preferred
+    /// first. If there is no description in any of them, the English description is used (the
+    /// languages requested with the "language" query parameter or the Accept-Language header are
+    /// tried first) [default: en]
+    #[clap(long, env = "POKESERVICE_LANGUAGES")]
+    pub languages: Option<String>,
+    /// Replace the capitalization of older games in the descriptions (i.e. "POKéMON") with the
+    /// modern spelling ("Pokémon")
+    #[clap(
+        long,
+        env = "POKESERVICE_NORMALISE_CASING",
+        min_values = 0,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    pub normalise_casing: Option<bool>,
+    /// Comma separated list of the styles of translation provided by the translation service (i.e.
+    /// "shakespeare,yoda"). The endpoint for each style is found next to the Shakespeare endpoint
+    /// (i.e. https://api.funtranslations.com/translate/yoda.json). The first style is used unless
+    /// another is requested with the "style" query parameter [default:
+    /// shakespeare,yoda,pirate,minion]
+    #[clap(long, env = "POKESERVICE_STYLES")]
+    pub styles: Option<String>,
+    /// Use the built-in, offline translator for the "shakespeare" style: "off" (never), "primary"
+    /// (instead of the translation service) or "fallback" (when the translation service fails)
+    /// [default: off]
+    #[clap(long, env = "POKESERVICE_OFFLINE_TRANSLATION")]
+    pub offline_translation: Option<String>,
+    /// Comma separated list of the translators to try, in order, for the "shakespeare" style:
+    /// "funtranslations-paid" (the translation service, using the API key), "funtranslations"
+    /// (the translation service, without the API key) or "dictionary" (the built-in, offline
+    /// translator). The next translator is tried if one is unavailable, rate limited or times out.
+    /// The other styles use the same chain, without the dictionary (this cannot be combined with
+    /// --offline-translation)
+    #[clap(long, env = "POKESERVICE_TRANSLATION_CHAIN")]
+    pub translation_chain: Option<String>,
+    /// The maximum length (in characters) of text sent to the translation service at once. Longer
+    /// text is split into chunks at the ends of sentences [default: 400]
+    #[clap(long, env = "POKESERVICE_TRANSLATION_CHUNK_SIZE")]
+    pub translation_chunk_size: Option<usize>,
+    /// The maximum number of chunks of a text that are translated concurrently [default: 2]
+    #[clap(long, env = "POKESERVICE_TRANSLATION_CONCURRENCY")]
+    pub translation_concurrency: Option<usize>,
+}
+
+/// A translator in a chain of translators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranslatorStep {
+    /// The paid tier of the translation service.
+    FunTranslationsPaid,
+    /// The free tier of the translation service.
+    FunTranslations,
+    /// The built-in, offline Shakespearian translator.
+    Dictionary,
+}
+
+impl TranslatorStep {
+    /// The name of the translator in the configuration and in responses.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TranslatorStep::FunTranslationsPaid => "funtranslations-paid",
+            TranslatorStep::FunTranslations => "funtranslations",
+            TranslatorStep::Dictionary => "dictionary",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        [
+            TranslatorStep::FunTranslationsPaid,
+            TranslatorStep::FunTranslations,
+            TranslatorStep::Dictionary,
+        ]
+        .iter()
+        .copied()
+        .find(|step| step.name() == name)
+    }
+}
+
+/// When the built-in, offline translator is used for Shakespearian translations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OfflineTranslation {
+    /// Only the translation service is used.
+    Off,
+    /// Only the offline translator is used.
+    Primary,
+    /// The offline translator is used when the translation service fails.
+    Fallback,
+}
+
+/// Timeouts for requests to a remote service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeouts {
+    pub connect: Duration,
+    pub request: Duration,
+}
+
+impl Timeouts {
+    fn from_millis(connect: u64, request: u64) -> Result<Self, String> {
+        if connect == 0 || request == 0 {
+            Err("Timeouts must be at least 1 millisecond.".to_string())
+        } else {
+            Ok(Timeouts {
+                connect: Duration::from_millis(connect),
+                request: Duration::from_millis(request),
+            })
+        }
+    }
+
+    /// Create an HTTP client that applies the timeouts.
+    pub fn make_client(&self) -> reqwest::Result<Client> {
+        Client::builder()
+            .connect_timeout(self.connect)
+            .timeout(self.request)
+            .build()
+    }
+}
+
+/// Validated configuration for the service.
+#[derive(Debug)]
+pub struct Config {
+    pub sock_addr: SocketAddr,
+    pub poke_api_url: Url,
+    pub shakespeare_url: Url,
+    pub cache_capacity: NonZeroUsize,
+    pub cache_ttl: Duration,
+    pub translation_store: Option<PathBuf>,
+    pub fallback: bool,
+    pub retry_policy: RetryPolicy,
+    pub breaker_config: BreakerConfig,
+    pub pokemon_timeouts: Timeouts,
+    pub shakespeare_timeouts: Timeouts,
+    pub deadline: Duration,
+    pub batch_concurrency: NonZeroUsize,
+    pub tls: Option<TlsConfig>,
+    pub shakespeare_api_key: Option<ApiKey>,
+    pub species_refresh: Duration,
+    pub auto_resolve: bool,
+    pub versions: Vec<String>,
+    pub languages: Vec<String>,
+    pub normalise_casing: bool,
+    pub styles: Vec<String>,
+    pub translation_chain: Vec<TranslatorStep>,
+    pub chunking: ChunkingConfig,
+}
+
+/// The API key is redacted from debug output, as it is by [`ApiKey`].
+impl Debug for Params {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let Params {
+            config,
+            bind,
+            port,
+            pokemon,
+            shakespeare,
+            cache_ttl,
+            cache_capacity,
+            translation_store,
+            fallback,
+            retry_attempts,
+            retry_delay,
+            retry_jitter,
+            retry_statuses,
+            breaker_threshold,
+            breaker_cool_down,
+            pokemon_connect_timeout,
+            pokemon_timeout,
+            shakespeare_connect_timeout,
+            shakespeare_timeout,
+            deadline,
+            batch_concurrency,
+            log,
+            tls_cert,
+            tls_key,
+            shakespeare_api_key,
+            shakespeare_api_key_file,
+            species_refresh,
+            auto_resolve,
+            versions,
+            languages,
+            normalise_casing,
+            styles,
+            offline_translation,
+            translation_chain,
+            translation_chunk_size,
+            translation_concurrency,
+        } = self;
+        f.debug_struct("Params")
+            .field("config", config)
+            .field("bind", bind)
+            .field("port", port)
+            .field("pokemon", pokemon)
+            .field("shakespeare", shakespeare)
+            .field("cache_ttl", cache_ttl)
+            .field("cache_capacity", cache_capacity)
+            .field("translation_store", translation_store)
+            .field("fallback", fallback)
+            .field("retry_attempts", retry_attempts)
+            .field("retry_delay", retry_delay)
+            .field("retry_jitter", retry_jitter)
+            .field("retry_statuses", retry_statuses)
+            .field("breaker_threshold", breaker_threshold)
+            .field("breaker_cool_down", breaker_cool_down)
+            .field("pokemon_connect_timeout", pokemon_connect_timeout)
+            .field("pokemon_timeout", pokemon_timeout)
+            .field("shakespeare_connect_timeout", shakespeare_connect_timeout)
+            .field("shakespeare_timeout", shakespeare_timeout)
+            .field("deadline", deadline)
+            .field("batch_concurrency", batch_concurrency)
+            .field("log", log)
+            .field("tls_cert", tls_cert)
+            .field("tls_key", tls_key)
+            .field(
+                "shakespeare_api_key",
+                &shakespeare_api_key.as_ref().map(|_| "<redacted>"),
+            )
+            .field("shakespeare_api_key_file", shakespeare_api_key_file)
+            .field("species_refresh", species_refresh)
+            .field("auto_resolve", auto_resolve)
+            .field("versions", versions)
+            .field("languages", languages)
+            .field("normalise_casing", normalise_casing)
+            .field("styles", styles)
+            .field("offline_translation", offline_translation)
+            .field("translation_chain", translation_chain)
+            .field("translation_chunk_size", translation_chunk_size)
+            .field("translation_concurrency", translation_concurrency)
+            .finish()
+    }
+}
+
+impl Params {
+    /// Fill in any parameters that were not provided from the configuration file, if one was
+    /// specified.
+    pub fn load(self) -> Result<Params, String> {
+        if let Some(path) = self.config.as_ref() {
+            let file = FileConfig::load(Path::new(path))?;
+            Ok(self.or(file.into()))
+        } else {
+            Ok(self)
+        }
+    }
+
+    /// Combine two sets of parameters, with values from this one taking precedence.
+    fn or(self, other: Params) -> Params {
+        Params {
+            config: self.config.or(other.config),
+            bind: self.bind.or(other.bind),
+            port: self.port.or(other.port),
+            pokemon: self.pokemon.or(other.pokemon),
+            shakespeare: self.shakespeare.or(other.shakespeare),
+            cache_ttl: self.cache_ttl.or(other.cache_ttl),
+            cache_capacity: self.cache_capacity.or(other.cache_capacity),
+            translation_store: self.translation_store.or(other.translation_store),
+            fallback: self.fallback.or(other.fallback),
+            retry_attempts: self.retry_attempts.or(other.retry_attempts),
+            retry_delay: self.retry_delay.or(other.retry_delay),
+            retry_jitter: self.retry_jitter.or(other.retry_jitter),
+            retry_statuses: self.retry_statuses.or(other.retry_statuses),
+            breaker_threshold: self.breaker_threshold.or(other.breaker_threshold),
+            breaker_cool_down: self.breaker_cool_down.or(other.breaker_cool_down),
+            pokemon_connect_timeout: self
+                .pokemon_connect_timeout
+                .or(other.pokemon_connect_timeout),
+            pokemon_timeout: self.pokemon_timeout.or(other.pokemon_timeout),
+            shakespeare_connect_timeout: self
+                .shakespeare_connect_timeout
+                .or(other.shakespeare_connect_timeout),
+            shakespeare_timeout: self.shakespeare_timeout.or(other.shakespeare_timeout),
+            deadline: self.deadline.or(other.deadline),
+            batch_concurrency: self.batch_concurrency.or(other.batch_concurrency),
+            log: self.log.or(other.log),
+            tls_cert: self.tls_cert.or(other.tls_cert),
+            tls_key: self.tls_key.or(other.tls_key),
+            shakespeare_api_key: self.shakespeare_api_key.or(other.shakespeare_api_key),
+            shakespeare_api_key_file: self
+                .shakespeare_api_key_file
+                .or(other.shakespeare_api_key_file),
+            species_refresh: self.species_refresh.or(other.species_refresh),
+            auto_resolve: self.auto_resolve.or(other.auto_resolve),
+            versions: self.versions.or(other.versions),
+            languages: self.languages.or(other.languages),
+            styles: self.styles.or(other.styles),
+            offline_translation: self.offline_translation.or(other.offline_translation),
+            translation_chain: self.translation_chain.or(other.translation_chain),
+            translation_chunk_size: self.translation_chunk_size.or(other.translation_chunk_size),
+            translation_concurrency: self
+                .translation_concurrency
+                .or(other.translation_concurrency),
+            normalise_casing: self.normalise_casing.or(other.normalise_casing),
+        }
+    }
+
+    /// The logging filter directives.
+    pub fn log_filter(&self) -> &str {
+        self.log.as_deref().unwrap_or(DEFAULT_LOG)
+    }
+
+    pub fn validate(self) -> Result<Config, String> {
+        let Params {
+            bind,
+            port,
+            pokemon,
+            shakespeare,
+            cache_ttl,
+            cache_capacity,
+            translation_store,
+            fallback,
+            retry_attempts,
+            retry_delay,
+            retry_jitter,
+            retry_statuses,
+            breaker_threshold,
+            breaker_cool_down,
+            pokemon_connect_timeout,
+            pokemon_timeout,
+            shakespeare_connect_timeout,
+            shakespeare_timeout,
+            deadline,
+            batch_concurrency,
+            log,
+            tls_cert,
+            tls_key,
+            shakespeare_api_key,
+            shakespeare_api_key_file,
+            species_refresh,
+            auto_resolve,
+            versions,
+            languages,
+            normalise_casing,
+            styles,
+            offline_translation,
+            translation_chain,
+            translation_chunk_size,
+            translation_concurrency,
+            ..
+        } = self;
+
+        let bind = bind.unwrap_or_else(|| DEFAULT_BIND.to_string());
+        let addr: IpAddr = bind
+            .parse()
+            .map_err(|_| format!("{} is not a valid IP.", bind))?;
+
+        let sock_addr = SocketAddr::new(addr, port.unwrap_or(DEFAULT_PORT));
+
+        let pokemon = pokemon.ok_or_else(|| {
+            "The URL of the Pokemon API is required (--pokemon or pokemon.url in the configuration file).".to_string()
+        })?;
+        let poke_api_url =
+            Url::parse(pokemon.as_str()).map_err(|_| format!("{} is not a valid URL.", pokemon))?;
+
+        let shakespeare = shakespeare.ok_or_else(|| {
+            "The URL of the Shakespeare translation service is required (--shakespeare or shakespeare.url in the configuration file).".to_string()
+        })?;
+        let shakespeare_url = Url::parse(shakespeare.as_str())
+            .map_err(|_| format!("{} is not a valid URL.", shakespeare))?;
+
+        let cache_capacity = NonZeroUsize::new(cache_capacity.unwrap_or(DEFAULT_CACHE_CAPACITY))
+            .ok_or_else(|| "The cache capacity must be at least 1.".to_string())?;
+
+        let chunking = ChunkingConfig {
+            max_chars: NonZeroUsize::new(
+                translation_chunk_size.unwrap_or(DEFAULT_TRANSLATION_CHUNK_SIZE),
+            )
+            .ok_or_else(|| "The translation chunk size must be at least 1.".to_string())?,
+            concurrency: NonZeroUsize::new(
+                translation_concurrency.unwrap_or(DEFAULT_TRANSLATION_CONCURRENCY),
+            )
+            .ok_or_else(|| "The translation concurrency must be at least 1.".to_string())?,
+        };
+
+        let retry_attempts = retry_attempts.unwrap_or(DEFAULT_RETRY_ATTEMPTS);
+        if retry_attempts == 0 {
+            return Err("The number of retry attempts must be at least 1.".to_string());
+        }
+
+        let retry_jitter = retry_jitter.unwrap_or(DEFAULT_RETRY_JITTER);
+        if !(0.0..=1.0).contains(&retry_jitter) {
+            return Err(format!(
+                "The retry jitter must be between 0 and 1 (was {}).",
+                retry_jitter
+            ));
+        }
+
+        let retryable = retry_statuses
+            .as_deref()
+            .unwrap_or(DEFAULT_RETRY_STATUSES)
+            .split(',')
+            .map(str::trim)
+            .filter(|status| !status.is_empty())
+            .map(|status| {
+                status
+                    .parse::<u16>()
+                    .ok()
+                    .and_then(|code| StatusCode::from_u16(code).ok())
+                    .ok_or_else(|| format!("{} is not a valid HTTP status code.", status))
+            })
+            .collect::<Result<_, _>>()?;
+
+        let retry_policy = RetryPolicy {
+            max_attempts: retry_attempts,
+            base_delay: Duration::from_millis(retry_delay.unwrap_or(DEFAULT_RETRY_DELAY)),
+            jitter: retry_jitter,
+            retryable,
+        };
+
+        let breaker_threshold = breaker_threshold.unwrap_or(DEFAULT_BREAKER_THRESHOLD);
+        if breaker_threshold == 0 {
+            return Err("The circuit breaker threshold must be at least 1.".to_string());
+        }
+
+        let breaker_config = BreakerConfig {
+            failure_threshold: breaker_threshold,
+            cool_down: Duration::from_secs(breaker_cool_down.unwrap_or(DEFAULT_BREAKER_COOL_DOWN)),
+        };
+
+        let pokemon_timeouts = Timeouts::from_millis(
+            pokemon_connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT),
+            pokemon_timeout.unwrap_or(DEFAULT_TIMEOUT),
+        )?;
+        let shakespeare_timeouts = Timeouts::from_millis(
+            shakespeare_connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT),
+            shakespeare_timeout.unwrap_or(DEFAULT_TIMEOUT),
+        )?;
+
+        let deadline = deadline.unwrap_or(DEFAULT_DEADLINE);
+        if deadline == 0 {
+            return Err("The request deadline must be at least 1 millisecond.".to_string());
+        }
+        let deadline = Duration::from_millis(deadline);
+        for (service, timeouts) in [
+            ("Pokemon API", &pokemon_timeouts),
+            ("Shakespeare translation service", &shakespeare_timeouts),
+        ] {
+            let longest = retry_policy.max_duration(timeouts.request);
+            if longest > deadline {
+                return Err(format!(
+                    "The request deadline ({} ms) is shorter than the time that the requests to the {} can take with retries ({} ms). Reduce the timeout or the number of retry attempts, or increase the deadline.",
+                    deadline.as_millis(),
+                    service,
+                    longest.as_millis()
+                ));
+            }
+        }
+        let batch_concurrency =
+            NonZeroUsize::new(batch_concurrency.unwrap_or(DEFAULT_BATCH_CONCURRENCY))
+                .ok_or_else(|| "The batch concurrency must be at least 1.".to_string())?;
+
+        if let Some(log) = log {
+            EnvFilter::try_new(log.as_str())
+                .map_err(|_| format!("{} is not a valid logging filter.", log))?;
+        }
+
+        let tls = match (tls_cert, tls_key) {
+            (Some(cert), Some(key)) => Some(TlsConfig {
+                cert_path: PathBuf::from(cert),
+                key_path: PathBuf::from(key),
+            }),
+            (None, None) => None,
+            _ => {
+                return Err(
+                    "Both a TLS certificate and a private key are required to serve HTTPS."
+                        .to_string(),
+                )
+            }
+        };
+
+        let shakespeare_api_key = match (shakespeare_api_key, shakespeare_api_key_file) {
+            (Some(_), Some(_)) => {
+                return Err(
+                    "Only one of an API key and an API key file may be provided for the Shakespeare translation service."
+                        .to_string(),
+                )
+            }
+            (Some(secret), None) => Some(api_key(secret.as_str())?),
+            (None, Some(path)) => {
+                let secret = std::fs::read_to_string(path.as_str())
+                    .map_err(|e| format!("Failed to read the API key file {}: {}", path, e))?;
+                Some(api_key(secret.trim())?)
+            }
+            (None, None) => None,
+        };
+
+        let species_refresh = species_refresh.unwrap_or(DEFAULT_SPECIES_REFRESH);
+        if species_refresh == 0 {
+            return Err("The species refresh period must be at least 1 second.".to_string());
+        }
+
+        let mut styles =
+            DescriptionPreferences::parse_list(styles.as_deref().unwrap_or(DEFAULT_STYLES));
+        if styles.is_empty() {
+            return Err("At least one style of translation is required.".to_string());
+        }
+        if let Some(style) = styles
+            .iter()
+            .find(|style| !style.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+        {
+            return Err(format!("{} is not a valid style of translation.", style));
+        }
+        let mut seen = HashSet::new();
+        styles.retain(|style| seen.insert(style.clone()));
+
+        let offline_translation = match offline_translation.as_deref().map(str::trim) {
+            None => OfflineTranslation::Off,
+            Some(mode) if mode.eq_ignore_ascii_case("off") => OfflineTranslation::Off,
+            Some(mode) if mode.eq_ignore_ascii_case("primary") => OfflineTranslation::Primary,
+            Some(mode) if mode.eq_ignore_ascii_case("fallback") => OfflineTranslation::Fallback,
+            Some(mode) => {
+                return Err(format!(
+                    "{} is not a valid mode for offline translation (off, primary or fallback).",
+                    mode
+                ))
+            }
+        };
+        let remote = if shakespeare_api_key.is_some() {
+            TranslatorStep::FunTranslationsPaid
+        } else {
+            TranslatorStep::FunTranslations
+        };
+        let translation_chain =
+            match translation_chain {
+                Some(_) if offline_translation != OfflineTranslation::Off => return Err(
+                    "Only one of a translation chain and offline translation may be configured."
+                        .to_string(),
+                ),
+                Some(chain) => DescriptionPreferences::parse_list(chain.as_str())
+                    .iter()
+                    .map(|name| {
+                        TranslatorStep::parse(name)
+                            .ok_or_else(|| format!("{} is not a valid translator.", name))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+                None => match offline_translation {
+                    OfflineTranslation::Off => vec![remote],
+                    OfflineTranslation::Primary => vec![TranslatorStep::Dictionary],
+                    OfflineTranslation::Fallback => vec![remote, TranslatorStep::Dictionary],
+                },
+            };
+        if translation_chain.is_empty() {
+            return Err("At least one translator is required.".to_string());
+        }
+        if translation_chain.contains(&TranslatorStep::FunTranslationsPaid)
+            && shakespeare_api_key.is_none()
+        {
+            return Err(
+                "The paid tier of the translation service requires an API key.".to_string(),
+            );
+        }
+        if translation_chain.contains(&TranslatorStep::Dictionary)
+            && !styles.iter().any(|style| style == SHAKESPEARE)
+        {
+            return Err(
+                "Offline translation requires the \"shakespeare\" style to be enabled.".to_string(),
+            );
+        }
+
+        Ok(Config {
+            sock_addr,
+            poke_api_url,
+            shakespeare_url,
+            cache_capacity,
+            cache_ttl: Duration::from_secs(cache_ttl.unwrap_or(DEFAULT_CACHE_TTL)),
+            translation_store: translation_store.map(PathBuf::from),
+            fallback: fallback.unwrap_or(false),
+            retry_policy,
+            breaker_config,
+            pokemon_timeouts,
+            shakespeare_timeouts,
+            deadline,
+            batch_concurrency,
+            tls,
+            shakespeare_api_key,
+            species_refresh: Duration::from_secs(species_refresh),
+            auto_resolve: auto_resolve.unwrap_or(false),
+            versions: versions
+                .as_deref()
+                .map(DescriptionPreferences::parse_list)
+                .unwrap_or_default(),
+            languages: languages
+                .as_deref()
+                .map(DescriptionPreferences::parse_list)
+                .unwrap_or_default(),
+            normalise_casing: normalise_casing.unwrap_or(false),
+            styles,
+            translation_chain,
+            chunking,
+        })
+    }
+}
+
+/// Validate an API key. The key itself is never included in the error.
+fn api_key(secret: &str) -> Result<ApiKey, String> {
+    if secret.is_empty() {
+        return Err("The API key for the Shakespeare translation service is empty.".to_string());
+    }
+    ApiKey::new(secret).ok_or_else(|| {
+        "The API key for the Shakespeare translation service contains invalid characters."
+            .to_string()
+    })
+}
@@ -0,0 +1,395 @@
+use crate::config::file::FileConfig;
+use crate::config::{Params, Timeouts, TranslatorStep};
+use clap::Clap;
+use reqwest::StatusCode;
+use std::time::Duration;
+
+const TOML_CONFIG: &str = r#"
+[server]
+bind = "0.0.0.0"
+port = 9000
+fallback = true
+
+[pokemon]
+url = "https://pokeapi.co/api/v2/pokemon-species"
+timeout = 2000
+versions = ["sword", "shield"]
+languages = ["de", "en"]
+
+[shakespeare]
+url = "https://api.funtranslations.com/translate/shakespeare.json"
+styles = ["shakespeare", "yoda"]
+offline = "fallback"
+
+[cache]
+ttl = 60
+
+[retry]
+statuses = [500, 503]
+
+[logging]
+level = "info"
+"#;
+
+const YAML_CONFIG: &str = r#"
+server:
+  bind: "0.0.0.0"
+  port: 9000
+  fallback: true
+pokemon:
+  url: "https://pokeapi.co/api/v2/pokemon-species"
+  timeout: 2000
+  versions: ["sword", "shield"]
+  languages: ["de", "en"]
+shakespeare:
+  url: "https://api.funtranslations.com/translate/shakespeare.json"
+  styles: ["shakespeare", "yoda"]
+  offline: "fallback"
+cache:
+  ttl: 60
+retry:
+  statuses: [500, 503]
+logging:
+  level: "info"
+"#;
+
+fn required_params() -> Params {
+    Params {
+        pokemon: Some("https://pokeapi.co/api/v2/pokemon-species".to_string()),
+        shakespeare: Some("https://api.funtranslations.com/translate/shakespeare.json".to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn parse_toml() {
+    let params: Params = FileConfig::from_toml(TOML_CONFIG).unwrap().into();
+    check_file_params(params);
+}
+
+#[test]
+fn parse_yaml() {
+    let params: Params = FileConfig::from_yaml(YAML_CONFIG).unwrap().into();
+    check_file_params(params);
+}
+
+fn check_file_params(params: Params) {
+    assert_eq!(params.bind, Some("0.0.0.0".to_string()));
+    assert_eq!(params.port, Some(9000));
+    assert_eq!(params.fallback, Some(true));
+    assert_eq!(params.pokemon_timeout, Some(2000));
+    assert_eq!(params.cache_ttl, Some(60));
+    assert_eq!(params.retry_statuses, Some("500,503".to_string()));
+    assert_eq!(params.log, Some("info".to_string()));
+    assert_eq!(params.versions, Some("sword,shield".to_string()));
+    assert_eq!(params.languages, Some("de,en".to_string()));
+    assert_eq!(params.styles, Some("shakespeare,yoda".to_string()));
+    assert_eq!(params.offline_translation, Some("fallback".to_string()));
+
+    let config = params.validate().unwrap();
+    assert_eq!(config.sock_addr.to_string(), "0.0.0.0:9000");
+    assert_eq!(
+        config.translation_chain,
+        vec![TranslatorStep::FunTranslations, TranslatorStep::Dictionary]
+    );
+    assert!(config.fallback);
+    assert_eq!(config.pokemon_timeouts.request, Duration::from_millis(2000));
+    assert_eq!(config.cache_ttl, Duration::from_secs(60));
+    assert_eq!(
+        config.versions,
+        vec!["sword".to_string(), "shield".to_string()]
+    );
+    assert_eq!(
+        config.retry_policy.retryable,
+        vec![
+            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::SERVICE_UNAVAILABLE
+        ]
+        .into_iter()
+        .collect()
+    );
+}
+
+#[test]
+fn unknown_fields_rejected() {
+    assert!(FileConfig::from_toml("[server]\nbnid = \"0.0.0.0\"").is_err());
+    assert!(FileConfig::from_toml("[srever]\nbind = \"0.0.0.0\"").is_err());
+}
+
+#[test]
+fn command_line_takes_precedence() {
+    let file: Params = FileConfig::from_toml(TOML_CONFIG).unwrap().into();
+    let command_line = Params {
+        port: Some(8081),
+        ..Default::default()
+    };
+
+    let params = command_line.or(file);
+
+    assert_eq!(params.port, Some(8081));
+    assert_eq!(params.bind, Some("0.0.0.0".to_string()));
+}
+
+#[test]
+fn defaults() {
+    let config = required_params().validate().unwrap();
+
+    assert_eq!(config.sock_addr.to_string(), "127.0.0.1:8080");
+    assert_eq!(config.cache_capacity.get(), 1024);
+    assert!(!config.fallback);
+    assert_eq!(config.retry_policy.max_attempts, 3);
+    assert_eq!(
+        config.shakespeare_timeouts,
+        Timeouts {
+            connect: Duration::from_millis(2000),
+            request: Duration::from_millis(4000),
+        }
+    );
+    assert_eq!(config.deadline, Duration::from_millis(30000));
+    assert!(config.versions.is_empty());
+    assert_eq!(
+        config.styles,
+        vec!["shakespeare", "yoda", "pirate", "minion"]
+    );
+    assert_eq!(
+        config.translation_chain,
+        vec![TranslatorStep::FunTranslations]
+    );
+    assert_eq!(config.chunking.max_chars.get(), 400);
+    assert_eq!(config.chunking.concurrency.get(), 2);
+    assert_eq!(config.batch_concurrency.get(), 4);
+}
+
+#[test]
+fn validation_errors() {
+    let missing_url = Params {
+        shakespeare: None,
+        ..required_params()
+    };
+    assert!(missing_url.validate().is_err());
+
+    let bad_ip = Params {
+        bind: Some("localhost".to_string()),
+        ..required_params()
+    };
+    assert_eq!(
+        bad_ip.validate().err(),
+        Some("localhost is not a valid IP.".to_string())
+    );
+
+    let bad_status = Params {
+        retry_statuses: Some("502,50x".to_string()),
+        ..required_params()
+    };
+    assert_eq!(
+        bad_status.validate().err(),
+        Some("50x is not a valid HTTP status code.".to_string())
+    );
+
+    let bad_jitter = Params {
+        retry_jitter: Some(1.5),
+        ..required_params()
+    };
+    assert!(bad_jitter.validate().is_err());
+
+    let short_deadline = Params {
+        pokemon_timeout: Some(10000),
+        ..required_params()
+    };
+    assert!(short_deadline.validate().is_err());
+
+    let missing_key = Params {
+        tls_cert: Some("server.crt".to_string()),
+        ..required_params()
+    };
+    assert!(missing_key.validate().is_err());
+
+    let bad_log = Params {
+        log: Some("pokeservice=loud".to_string()),
+        ..required_params()
+    };
+    assert!(bad_log.validate().is_err());
+
+    let no_styles = Params {
+        styles: Some(" , ".to_string()),
+        ..required_params()
+    };
+    assert!(no_styles.validate().is_err());
+
+    let bad_style = Params {
+        styles: Some("yoda,../admin".to_string()),
+        ..required_params()
+    };
+    assert_eq!(
+        bad_style.validate().err(),
+        Some("../admin is not a valid style of translation.".to_string())
+    );
+
+    let zero_chunk_size = Params {
+        translation_chunk_size: Some(0),
+        ..required_params()
+    };
+    assert!(zero_chunk_size.validate().is_err());
+
+    let bad_offline = Params {
+        offline_translation: Some("sometimes".to_string()),
+        ..required_params()
+    };
+    assert!(bad_offline.validate().is_err());
+
+    let offline_without_shakespeare = Params {
+        offline_translation: Some("primary".to_string()),
+        styles: Some("yoda".to_string()),
+        ..required_params()
+    };
+    assert!(offline_without_shakespeare.validate().is_err());
+
+    let chain_and_offline = Params {
+        offline_translation: Some("primary".to_string()),
+        translation_chain: Some("dictionary".to_string()),
+        ..required_params()
+    };
+    assert!(chain_and_offline.validate().is_err());
+
+    let paid_without_key = Params {
+        translation_chain: Some("funtranslations-paid,dictionary".to_string()),
+        ..required_params()
+    };
+    assert!(paid_without_key.validate().is_err());
+
+    let unknown_translator = Params {
+        translation_chain: Some("funtranslations,babelfish".to_string()),
+        ..required_params()
+    };
+    assert_eq!(
+        unknown_translator.validate().err(),
+        Some("babelfish is not a valid translator.".to_string())
+    );
+}
+
+#[test]
+fn translation_chain() {
+    let params = Params {
+        shakespeare_api_key: Some("top-secret".to_string()),
+        translation_chain: Some("funtranslations-paid, funtranslations, dictionary".to_string()),
+        ..required_params()
+    };
+    assert_eq!(
+        params.validate().unwrap().translation_chain,
+        vec![
+            TranslatorStep::FunTranslationsPaid,
+            TranslatorStep::FunTranslations,
+            TranslatorStep::Dictionary
+        ]
+    );
+
+    // By default, the API key is used if there is one.
+    let params = Params {
+        shakespeare_api_key: Some("top-secret".to_string()),
+        offline_translation: Some("fallback".to_string()),
+        ..required_params()
+    };
+    assert_eq!(
+        params.validate().unwrap().translation_chain,
+        vec![
+            TranslatorStep::FunTranslationsPaid,
+            TranslatorStep::Dictionary
+        ]
+    );
+}
+
+#[test]
+fn environment_variables() {
+    std::env::set_var("POKESERVICE_PORT", "9001");
+    std::env::set_var("POKESERVICE_BREAKER_THRESHOLD", "7");
+
+    let params = Params::try_parse_from(vec!["pokeservice", "--port", "9002"]).unwrap();
+
+    std::env::remove_var("POKESERVICE_PORT");
+    std::env::remove_var("POKESERVICE_BREAKER_THRESHOLD");
+
+    // The command line takes precedence over the environment.
+    assert_eq!(params.port, Some(9002));
+    assert_eq!(params.breaker_threshold, Some(7));
+}
+
+#[test]
+fn fallback_switch() {
+    let params =
+        Params::try_parse_from(vec!["pokeservice", "--fallback", "--port", "9002"]).unwrap();
+    assert_eq!(params.fallback, Some(true));
+    assert_eq!(params.port, Some(9002));
+
+    let params = Params::try_parse_from(vec!["pokeservice", "--fallback=false"]).unwrap();
+    assert_eq!(params.fallback, Some(false));
+
+    // Without the switch, the value from the configuration file applies.
+    let params = Params::try_parse_from(vec!["pokeservice"]).unwrap();
+    assert_eq!(params.fallback, None);
+
+    std::env::set_var("POKESERVICE_FALLBACK", "true");
+    let params = Params::try_parse_from(vec!["pokeservice"]).unwrap();
+    std::env::remove_var("POKESERVICE_FALLBACK");
+    assert_eq!(params.fallback, Some(true));
+}
+
+#[test]
+fn auto_resolve_switch() {
+    let params =
+        Params::try_parse_from(vec!["pokeservice", "--auto-resolve", "--port", "9002"]).unwrap();
+    assert_eq!(params.auto_resolve, Some(true));
+    assert_eq!(params.port, Some(9002));
+
+    let params = Params::try_parse_from(vec!["pokeservice", "--auto-resolve=false"]).unwrap();
+    assert_eq!(params.auto_resolve, Some(false));
+}
+
+#[test]
+fn normalise_casing_switch() {
+    let params =
+        Params::try_parse_from(vec!["pokeservice", "--normalise-casing", "--port", "9002"])
+            .unwrap();
+    assert_eq!(params.normalise_casing, Some(true));
+    assert_eq!(params.port, Some(9002));
+
+    let params = Params::try_parse_from(vec!["pokeservice", "--normalise-casing=false"]).unwrap();
+    assert_eq!(params.normalise_casing, Some(false));
+}
+
+#[test]
+fn api_key_redacted() {
+    let params = Params {
+        shakespeare_api_key: Some("top-secret".to_string()),
+        ..required_params()
+    };
+    let debug = format!("{:?}", params);
+    assert!(debug.contains("shakespeare_api_key"));
+    assert!(!debug.contains("top-secret"));
+
+    let file = FileConfig::from_toml("[shakespeare]\napi_key = \"top-secret\"").unwrap();
+    assert!(!format!("{:?}", file).contains("top-secret"));
+}
+
+#[test]
+fn api_key_file() {
+    let path = std::env::temp_dir().join(format!("pokeservice-api-key-{}", std::process::id()));
+    std::fs::write(&path, "top-secret\n").unwrap();
+
+    let params = Params {
+        shakespeare_api_key_file: Some(path.to_string_lossy().to_string()),
+        ..required_params()
+    };
+    let config = params.validate().unwrap();
+    assert!(config.shakespeare_api_key.is_some());
+    assert!(!format!("{:?}", config).contains("top-secret"));
+
+    let both = Params {
+        shakespeare_api_key: Some("top-secret".to_string()),
+        shakespeare_api_key_file: Some(path.to_string_lossy().to_string()),
+        ..required_params()
+    };
+    let error = both.validate().err().unwrap();
+    assert!(!error.contains("top-secret"));
+
+    std::fs::remove_file(path).unwrap();
+}
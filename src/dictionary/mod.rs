@@ -0,0 +1,133 @@
+use crate::services::translation::{TranslationError, TranslationService};
+use futures::future::{ready, BoxFuture};
+use futures::FutureExt;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod tests;
+
+lazy_static! {
+    /// A word, possibly containing apostrophes (for example "you're").
+    static ref WORD: Regex = Regex::new(r"\p{L}+(?:['’]\p{L}+)*").unwrap();
+}
+
+/// The bundled dictionary for Shakespearian translations.
+const SHAKESPEARE_DICTIONARY: &str = include_str!("shakespeare.json");
+
+/// A translation service that works without a network connection, replacing the words and phrases
+/// of the text from a dictionary. The case of each replaced word and all of the punctuation of the
+/// text are preserved.
+pub struct DictionaryTranslationService {
+    /// Replacements keyed by the (lower case) word or phrase.
+    entries: HashMap<String, String>,
+    /// The number of words in the longest phrase in the dictionary.
+    max_words: usize,
+}
+
+impl DictionaryTranslationService {
+    /// # Arguments
+    /// * `entries` - Replacements for words and phrases (with words separated by single spaces).
+    ///   The case of the words is ignored.
+    pub fn new(entries: HashMap<String, String>) -> Self {
+        let entries = entries
+            .into_iter()
+            .map(|(phrase, replacement)| (normalise(phrase.as_str()), replacement))
+            .collect::<HashMap<_, _>>();
+        let max_words = entries
+            .keys()
+            .map(|phrase| phrase.split(' ').count())
+            .max()
+            .unwrap_or(0);
+        DictionaryTranslationService { entries, max_words }
+    }
+
+    /// A service that translates into faux Shakespearian English using the bundled dictionary.
+    pub fn shakespeare() -> Self {
+        let entries = serde_json::from_str(SHAKESPEARE_DICTIONARY)
+            .expect("The bundled dictionary is invalid.");
+        DictionaryTranslationService::new(entries)
+    }
+
+    /// Translate the text. The longest phrase from the dictionary that starts at each word is
+    /// replaced. The words of a phrase must only be separated by whitespace in the text.
+    pub fn translate(&self, text: &str) -> String {
+        let words = WORD.find_iter(text).collect::<Vec<_>>();
+        let mut translated = String::with_capacity(text.len());
+        let mut last = 0;
+        let mut i = 0;
+        while i < words.len() {
+            let replacement = (1..=self.max_words.min(words.len() - i))
+                .rev()
+                .filter(|n| {
+                    words[i..i + n]
+                        .windows(2)
+                        .all(|pair| text[pair[0].end()..pair[1].start()].trim().is_empty())
+                })
+                .find_map(|n| {
+                    let phrase = words[i..i + n]
+                        .iter()
+                        .map(|word| normalise(word.as_str()))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    self.entries
+                        .get(&phrase)
+                        .map(|replacement| (n, replacement))
+                });
+            if let Some((n, replacement)) = replacement {
+                let start = words[i].start();
+                let end = words[i + n - 1].end();
+                translated.push_str(&text[last..start]);
+                translated.push_str(&match_case(&text[start..end], replacement));
+                last = end;
+                i += n;
+            } else {
+                i += 1;
+            }
+        }
+        translated.push_str(&text[last..]);
+        translated
+    }
+}
+
+impl TranslationService for DictionaryTranslationService {
+    fn attempt_translation<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<String, TranslationError>> {
+        ready(Ok(self.translate(text))).boxed()
+    }
+}
+
+/// The form of a word or phrase used to look it up in the dictionary.
+fn normalise(phrase: &str) -> String {
+    phrase.to_lowercase().replace('’', "'")
+}
+
+/// Give the replacement the case of the original text. If the original is all upper case (and
+/// longer than a single letter) so is the replacement. Otherwise, if the original starts with a
+/// capital letter, so does the replacement.
+fn match_case(original: &str, replacement: &str) -> String {
+    let mut letters = original.chars().filter(|c| c.is_alphabetic());
+    let first_upper = matches!(letters.next(), Some(c) if c.is_uppercase());
+    let rest = letters.collect::<Vec<_>>();
+    if first_upper && !rest.is_empty() && rest.iter().all(|c| c.is_uppercase()) {
+        replacement.to_uppercase()
+    } else if first_upper {
+        // The first letter of the replacement may follow an apostrophe (for example "'tis").
+        let mut capitalised = String::with_capacity(replacement.len());
+        let mut done = false;
+        for c in replacement.chars() {
+            if !done && c.is_alphabetic() {
+                capitalised.extend(c.to_uppercase());
+                done = true;
+            } else {
+                capitalised.push(c);
+            }
+        }
+        capitalised
+    } else {
+        replacement.to_string()
+    }
+}
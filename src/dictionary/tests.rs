@@ -0,0 +1,67 @@
+use crate::dictionary::DictionaryTranslationService;
+use crate::services::translation::TranslationService;
+use std::collections::HashMap;
+
+#[test]
+fn bundled_dictionary() {
+    let service = DictionaryTranslationService::shakespeare();
+    assert_eq!(
+        service.translate("Are you sure your friend has your map?"),
+        "Art thou sure thy friend hath thy map?"
+    );
+}
+
+#[test]
+fn longest_phrase_replaced() {
+    let service = DictionaryTranslationService::shakespeare();
+    assert_eq!(
+        service.translate("you are kind and you have courage"),
+        "thou art kind and thou hast courage"
+    );
+}
+
+#[test]
+fn phrases_not_matched_across_punctuation() {
+    let service = DictionaryTranslationService::shakespeare();
+    assert_eq!(
+        service.translate("Thank you, are they?"),
+        "Thank thee, art they?"
+    );
+}
+
+#[test]
+fn case_preserved() {
+    let service = DictionaryTranslationService::shakespeare();
+    assert_eq!(service.translate("YOU ARE"), "THOU ART");
+    assert_eq!(service.translate("It is here."), "'Tis here.");
+    assert_eq!(service.translate("You're late!"), "Thou art late!");
+    assert_eq!(service.translate("you’re late"), "thou art late");
+}
+
+#[test]
+fn punctuation_preserved() {
+    let service = DictionaryTranslationService::shakespeare();
+    assert_eq!(
+        service.translate("\"Hello,\" it says... (very quietly)."),
+        "\"Good morrow,\" it saith... (most quietly)."
+    );
+}
+
+#[test]
+fn words_within_words_not_replaced() {
+    let mut entries = HashMap::new();
+    entries.insert("Are".to_string(), "art".to_string());
+    let service = DictionaryTranslationService::new(entries);
+    assert_eq!(service.translate("Careful, you are."), "Careful, you art.");
+}
+
+#[tokio::test]
+async fn never_fails() {
+    let service = DictionaryTranslationService::shakespeare();
+    assert_eq!(
+        service
+            .attempt_translation("It stores electricity in its cheeks.")
+            .await,
+        Ok("It stores electricity in its cheeks.".to_string())
+    );
+}
@@ -1,86 +1,704 @@
-use crate::model::{ErrorMessage, PokemonData};
-use crate::services::pokemon::{PokemonService, PokemonServiceError};
-use crate::services::translation::{TranslationError, TranslationService};
+use crate::cache::TtlCache;
+use crate::circuit_breaker::Circuit;
+use crate::model::{
+    BatchEntry, ErrorMessage, PokemonBatch, PokemonData, PokemonDescriptions, ServiceStatus,
+};
+use crate::names::to_slug;
+use crate::services::pokemon::{
+    DescriptionPreferences, DescriptionsService, PokemonService, PokemonServiceError,
+};
+use crate::services::translation::{
+    RateLimit, TranslationError, TranslationService, Translator, TranslatorRegistry,
+    SOURCE_LANGUAGE,
+};
+use crate::tls::ReloadableCertResolver;
+use futures::StreamExt;
+use percent_encoding::percent_decode_str;
+use reqwest::header::{ACCEPT_LANGUAGE, RETRY_AFTER};
 use reqwest::StatusCode;
+use serde::Deserialize;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::future::Future;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
 use tracing::{event, Level};
-use warp::reject::Reject;
+use warp::body::BodyDeserializeError;
+use warp::reject::{PayloadTooLarge, Reject};
 use warp::reply::Response;
 use warp::{Filter, Rejection, Reply};
 
 #[cfg(test)]
 mod tests;
 
-/// Create the warp filter for the single endpoint and execute it.
+/// Cache for the final (translated) responses from the endpoint, keyed by Pokemon name, the
+/// preferences for the description and the style of translation.
+pub type ResponseCache = TtlCache<String, PokemonData>;
+
+/// Cache for the (optionally translated) responses from the descriptions endpoint, keyed by
+/// Pokemon name and the style of translation (if the descriptions were translated).
+pub type DescriptionsCache = TtlCache<String, PokemonDescriptions>;
+
+/// The caches for the responses of each of the endpoints.
+pub struct ResponseCaches {
+    pub pokemon: ResponseCache,
+    pub descriptions: DescriptionsCache,
+}
+
+/// Options that can be provided, for a single request, as query parameters.
+#[derive(Deserialize, Debug, Default)]
+struct RequestOptions {
+    /// Overrides whether the untranslated description is returned when the translation service
+    /// is unavailable.
+    fallback: Option<bool>,
+    /// Overrides the preferred versions of the game from which the description is taken (as a
+    /// comma separated list).
+    version: Option<String>,
+    /// The preferred languages of the description (as a comma separated list). This takes
+    /// precedence over the Accept-Language header.
+    language: Option<String>,
+    /// The style of translation (for example "yoda"), if not the default.
+    style: Option<String>,
+}
+
+/// The body of a request to the batch endpoint.
+#[derive(Deserialize, Debug)]
+struct BatchRequest {
+    names: Vec<String>,
+}
+
+/// The names for the batch endpoint, as a query parameter (a comma separated list).
+#[derive(Deserialize, Debug)]
+struct BatchQuery {
+    /// A missing list is treated as an empty one, which is rejected with a clear error.
+    #[serde(default)]
+    names: String,
+}
+
+/// The maximum number of names in a single batch request.
+const MAX_BATCH_NAMES: usize = 100;
+
+/// The maximum size (in bytes) of the body of a batch request.
+const MAX_BATCH_BODY: u64 = 16 * 1024;
+
+/// Options for the descriptions endpoint that can be provided as query parameters.
+#[derive(Deserialize, Debug, Default)]
+struct DescriptionsOptions {
+    /// Whether to translate the descriptions.
+    translate: Option<bool>,
+    /// The style of translation (for example "yoda"), if not the default.
+    style: Option<String>,
+}
+
+/// Settings that control how requests to the endpoints are handled.
+#[derive(Debug, Clone)]
+pub struct EndpointSettings {
+    /// Whether to return the untranslated description when the translation service is
+    /// unavailable, by default (this can be overridden for each request).
+    pub fallback: bool,
+    /// The maximum time to spend handling a request (including a batch request, for which any
+    /// Pokemon not found by the deadline are reported as timed out).
+    pub deadline: Duration,
+    /// The maximum number of Pokemon looked up concurrently for a batch request.
+    pub batch_concurrency: usize,
+    /// Preferences for choosing the description of a Pokemon, by default (these can be overridden
+    /// for each request).
+    pub preferences: DescriptionPreferences,
+}
+
+/// Create the warp filters for the endpoints and execute them.
 ///
 /// # Arguments
 ///
 /// * `socket_addr` - Address to bind to.
+/// * `tls` - Provides the certificate if the endpoints are to be served over HTTPS.
 /// * `pokemon_service` - A service implementation to get descriptions for Pokemon species.
-/// * `translation_service`- A service implementation to transform the descriptions.
+/// * `translators`- Service implementations to transform the descriptions, for each style of
+///   translation.
+/// * `caches` - Caches for responses, allowing repeated requests to skip both services.
+/// * `circuits` - Circuit breakers protecting the services, to be reported by the status endpoint.
+/// * `settings` - Settings for handling requests.
 pub async fn run_server<Poke, Trans>(
     socket_addr: SocketAddr,
+    tls: Option<Arc<ReloadableCertResolver>>,
     pokemon_service: Poke,
-    translation_service: Trans,
+    translators: TranslatorRegistry<Trans>,
+    caches: ResponseCaches,
+    circuits: Vec<Arc<Circuit>>,
+    settings: EndpointSettings,
 ) where
-    Poke: PokemonService + Send + Sync + 'static,
+    Poke: PokemonService + DescriptionsService + Send + Sync + 'static,
     Trans: TranslationService + Send + Sync + 'static,
 {
-    let endpoint =
-        make_endpoint_filter(pokemon_service, translation_service).recover(handle_rejection);
+    let endpoint = make_routes(pokemon_service, translators, caches, circuits, settings);
+
+    if let Some(resolver) = tls {
+        let listener = match TcpListener::bind(socket_addr).await {
+            Ok(listener) => listener,
+            Err(error) => {
+                event!(Level::ERROR, message = "Failed to bind to the socket address.", %socket_addr, %error);
+                panic!("{}", error);
+            }
+        };
+        warp::serve(endpoint)
+            .run_incoming(crate::tls::incoming(listener, resolver.acceptor()))
+            .await
+    } else {
+        warp::serve(endpoint).run(socket_addr).await
+    }
+}
 
-    warp::serve(endpoint).run(socket_addr).await
+/// All of the endpoints of the service, with rejections converted to error responses.
+fn make_routes<Poke, Trans>(
+    pokemon_service: Poke,
+    translators: TranslatorRegistry<Trans>,
+    caches: ResponseCaches,
+    circuits: Vec<Arc<Circuit>>,
+    settings: EndpointSettings,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
+where
+    Poke: PokemonService + DescriptionsService + Send + Sync + 'static,
+    Trans: TranslationService + Send + Sync + 'static,
+{
+    let ResponseCaches {
+        pokemon: cache,
+        descriptions: descriptions_cache,
+    } = caches;
+    let pokemon_service = Arc::new(pokemon_service);
+    let translators = Arc::new(translators);
+    let cache = Arc::new(cache);
+    let deadline = settings.deadline;
+    make_batch_filter(
+        pokemon_service.clone(),
+        translators.clone(),
+        cache.clone(),
+        settings.clone(),
+    )
+    .or(make_endpoint_filter(
+        pokemon_service.clone(),
+        translators.clone(),
+        cache,
+        settings,
+    ))
+    .or(make_descriptions_filter(
+        pokemon_service,
+        translators,
+        descriptions_cache,
+        deadline,
+    ))
+    .or(make_status_filter(circuits))
+    .recover(handle_rejection)
 }
 
 fn make_endpoint_filter<Poke, Trans>(
-    pokemon_service: Poke,
-    translation_service: Trans,
+    pokemon_service: Arc<Poke>,
+    translators: Arc<TranslatorRegistry<Trans>>,
+    cache: Arc<ResponseCache>,
+    settings: EndpointSettings,
 ) -> impl Filter<Extract = (PokemonData,), Error = Rejection> + Clone
 where
     Poke: PokemonService + Send + Sync + 'static,
     Trans: TranslationService + Send + Sync + 'static,
 {
-    let shared_pokemon_service = Arc::new(pokemon_service);
-    let shared_translation_service = Arc::new(translation_service);
+    let pokemon_service_filter = warp::any().map(move || pokemon_service.clone());
+    let translators_filter = warp::any().map(move || translators.clone());
+    let cache_filter = warp::any().map(move || cache.clone());
 
-    let pokemon_service_filter = warp::any().map(move || shared_pokemon_service.clone());
-    let shared_translation_service = warp::any().map(move || shared_translation_service.clone());
+    // Only GET, so that a rejected POST to the batch endpoint is not taken as a request for a
+    // Pokemon named "batch".
+    warp::get()
+        .and(warp::path!("pokemon" / String))
+        .map(decode_path_segment)
+        .and(warp::query::<RequestOptions>())
+        .and(warp::header::optional::<String>(ACCEPT_LANGUAGE.as_str()))
+        .and(pokemon_service_filter)
+        .and(translators_filter)
+        .and(cache_filter)
+        .and_then(
+            move |name,
+                  options: RequestOptions,
+                  accept_language,
+                  pokemon,
+                  translators: Arc<TranslatorRegistry<Trans>>,
+                  cache| {
+                let EndpointSettings {
+                    fallback,
+                    deadline,
+                    preferences,
+                    ..
+                } = settings.clone();
+                async move {
+                    let fallback = options.fallback.unwrap_or(fallback);
+                    let translator = find_translator(&translators, options.style.as_deref())
+                        .map_err(warp::reject::custom)?;
+                    let preferences = request_preferences(preferences, options, accept_language);
+                    let request =
+                        handle_request(name, preferences, fallback, pokemon, translator, cache);
+                    within_deadline(deadline, request).await
+                }
+            },
+        )
+}
+
+/// The batch endpoint accepts the names either as a JSON body (POST /pokemon/batch) or as a query
+/// parameter (GET /pokemon?names=a,b,c). The other query parameters apply to every name.
+fn make_batch_filter<Poke, Trans>(
+    pokemon_service: Arc<Poke>,
+    translators: Arc<TranslatorRegistry<Trans>>,
+    cache: Arc<ResponseCache>,
+    settings: EndpointSettings,
+) -> impl Filter<Extract = (PokemonBatch,), Error = Rejection> + Clone
+where
+    Poke: PokemonService + Send + Sync + 'static,
+    Trans: TranslationService + Send + Sync + 'static,
+{
+    let pokemon_service_filter = warp::any().map(move || pokemon_service.clone());
+    let translators_filter = warp::any().map(move || translators.clone());
+    let cache_filter = warp::any().map(move || cache.clone());
 
-    warp::path!("pokemon" / String)
+    let from_body = warp::post()
+        .and(warp::path!("pokemon" / "batch"))
+        .and(warp::body::content_length_limit(MAX_BATCH_BODY))
+        .and(warp::body::json::<BatchRequest>())
+        .map(|request: BatchRequest| request.names);
+    let from_query = warp::get()
+        .and(warp::path!("pokemon"))
+        .and(warp::query::<BatchQuery>())
+        .map(|query: BatchQuery| {
+            query
+                .names
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        });
+
+    from_body
+        .or(from_query)
+        .unify()
+        .and(warp::query::<RequestOptions>())
+        .and(warp::header::optional::<String>(ACCEPT_LANGUAGE.as_str()))
         .and(pokemon_service_filter)
-        .and(shared_translation_service)
-        .and_then(|name, pokemon, trans| async move {
-            handle_request(name, pokemon, trans)
-                .await
-                .map_err(warp::reject::custom)
+        .and(translators_filter)
+        .and(cache_filter)
+        .and_then(
+            move |names: Vec<String>,
+                  options: RequestOptions,
+                  accept_language,
+                  pokemon: Arc<Poke>,
+                  translators: Arc<TranslatorRegistry<Trans>>,
+                  cache: Arc<ResponseCache>| {
+                let EndpointSettings {
+                    fallback,
+                    deadline,
+                    batch_concurrency,
+                    preferences,
+                } = settings.clone();
+                async move {
+                    let fallback = options.fallback.unwrap_or(fallback);
+                    let translator = find_translator(&translators, options.style.as_deref())
+                        .map_err(warp::reject::custom)?;
+                    let preferences = request_preferences(preferences, options, accept_language);
+                    let lookup = |name: String| {
+                        let request = handle_request(
+                            name,
+                            preferences.clone(),
+                            fallback,
+                            pokemon.clone(),
+                            translator.clone(),
+                            cache.clone(),
+                        );
+                        limit_time(deadline, request)
+                    };
+                    handle_batch_request(names, batch_concurrency, deadline, lookup)
+                        .await
+                        .map_err(warp::reject::custom)
+                }
+            },
+        )
+}
+
+fn make_descriptions_filter<Poke, Trans>(
+    pokemon_service: Arc<Poke>,
+    translators: Arc<TranslatorRegistry<Trans>>,
+    cache: DescriptionsCache,
+    deadline: Duration,
+) -> impl Filter<Extract = (PokemonDescriptions,), Error = Rejection> + Clone
+where
+    Poke: DescriptionsService + Send + Sync + 'static,
+    Trans: TranslationService + Send + Sync + 'static,
+{
+    let shared_cache = Arc::new(cache);
+
+    let pokemon_service_filter = warp::any().map(move || pokemon_service.clone());
+    let translators_filter = warp::any().map(move || translators.clone());
+    let cache_filter = warp::any().map(move || shared_cache.clone());
+
+    warp::get()
+        .and(warp::path!("pokemon" / String / "descriptions"))
+        .map(decode_path_segment)
+        .and(warp::query::<DescriptionsOptions>())
+        .and(pokemon_service_filter)
+        .and(translators_filter)
+        .and(cache_filter)
+        .and_then(
+            move |name,
+                  options: DescriptionsOptions,
+                  pokemon,
+                  translators: Arc<TranslatorRegistry<Trans>>,
+                  cache| async move {
+                let translator = if options.translate.unwrap_or(false) {
+                    Some(
+                        find_translator(&translators, options.style.as_deref())
+                            .map_err(warp::reject::custom)?,
+                    )
+                } else {
+                    None
+                };
+                let request = handle_descriptions_request(name, translator, pokemon, cache);
+                within_deadline(deadline, request).await
+            },
+        )
+}
+
+/// Find the translator for the requested style of translation (or the default style).
+fn find_translator<Trans>(
+    translators: &TranslatorRegistry<Trans>,
+    style: Option<&str>,
+) -> Result<Translator<Trans>, ServiceError> {
+    translators.get(style).ok_or_else(|| {
+        ServiceError::UnknownStyle(style.unwrap_or_default().to_string(), translators.styles())
+    })
+}
+
+/// Reject a request if it is not completed within the deadline.
+async fn within_deadline<T, F>(deadline: Duration, request: F) -> Result<T, Rejection>
+where
+    F: Future<Output = Result<T, ServiceError>>,
+{
+    limit_time(deadline, request)
+        .await
+        .map_err(warp::reject::custom)
+}
+
+/// Fail a request if it is not completed within the deadline.
+async fn limit_time<T, F>(deadline: Duration, request: F) -> Result<T, ServiceError>
+where
+    F: Future<Output = Result<T, ServiceError>>,
+{
+    match tokio::time::timeout(deadline, request).await {
+        Ok(result) => result,
+        Err(_) => {
+            event!(
+                Level::WARN,
+                message = "Request deadline expired.",
+                deadline_ms = deadline.as_millis() as u64
+            );
+            Err(ServiceError::Timeout)
+        }
+    }
+}
+
+/// Combine the preferences for the description, given for a single request, with the defaults.
+///
+/// # Arguments
+/// * `defaults` - The default preferences of the service.
+/// * `options` - The query parameters of the request.
+/// * `accept_language` - The Accept-Language header of the request.
+fn request_preferences(
+    defaults: DescriptionPreferences,
+    options: RequestOptions,
+    accept_language: Option<String>,
+) -> DescriptionPreferences {
+    let DescriptionPreferences {
+        versions,
+        languages: default_languages,
+    } = defaults;
+    let versions = options
+        .version
+        .as_deref()
+        .map(DescriptionPreferences::parse_list)
+        .unwrap_or(versions);
+
+    // The requested languages are tried first, falling back to the defaults.
+    let mut languages = if let Some(list) = options.language.as_deref() {
+        DescriptionPreferences::parse_list(list)
+    } else if let Some(header) = accept_language.as_deref() {
+        parse_accept_language(header)
+    } else {
+        vec![]
+    };
+    for language in default_languages {
+        if !languages.contains(&language) {
+            languages.push(language);
+        }
+    }
+    DescriptionPreferences {
+        versions,
+        languages,
+    }
+}
+
+/// The languages from an Accept-Language header (for example "de-DE,de;q=0.9,en;q=0.8"), the most
+/// preferred first. Wildcards and languages with a weight of 0 are ignored.
+fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut weighted = header
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';').map(str::trim);
+            let language = parts.next().filter(|l| !l.is_empty() && *l != "*")?;
+            let weight = parts
+                .find_map(|param| param.strip_prefix("q="))
+                .map(|q| q.parse::<f32>().unwrap_or(0.0))
+                .unwrap_or(1.0);
+            if weight > 0.0 {
+                Some((language.to_lowercase(), weight))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    // The sort is stable so languages of equal weight keep the order of the header.
+    weighted.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+    let mut languages: Vec<String> = vec![];
+    for (language, _) in weighted {
+        if !languages.contains(&language) {
+            languages.push(language);
+        }
+    }
+    languages
+}
+
+/// Path parameters are not decoded by warp so names that contain characters that must be escaped
+/// in a URL (for example "#025") would not otherwise be recognized.
+fn decode_path_segment(segment: String) -> String {
+    percent_decode_str(segment.as_str())
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+fn make_status_filter(
+    circuits: Vec<Arc<Circuit>>,
+) -> impl Filter<Extract = (ServiceStatus,), Error = Rejection> + Clone {
+    let circuits = Arc::new(circuits);
+    warp::path!("status")
+        .and(warp::get())
+        .map(move || ServiceStatus {
+            circuits: circuits.iter().map(|circuit| circuit.status()).collect(),
         })
 }
 
 async fn handle_request<Poke, Trans>(
     name: String,
+    preferences: DescriptionPreferences,
+    fallback: bool,
     pokemon_service: Arc<Poke>,
-    translation_service: Arc<Trans>,
+    translator: Translator<Trans>,
+    cache: Arc<ResponseCache>,
 ) -> Result<PokemonData, ServiceError>
 where
     Poke: PokemonService,
     Trans: TranslationService,
 {
-    event!(Level::INFO, message = "Handling request.", %name);
-    let mut response = pokemon_service.get_pokemon(name.as_str()).await?;
-    response.description = translation_service
-        .attempt_translation(response.description.as_str())
+    let Translator {
+        style,
+        service: translation_service,
+    } = translator;
+    event!(Level::INFO, message = "Handling request.", %name, %preferences, %style);
+    let key = format!("{}?{}&style={}", to_slug(name.as_str()), preferences, style);
+    if let Some(response) = cache.get(&key) {
+        return Ok(response);
+    }
+    let mut response = pokemon_service
+        .get_pokemon(name.as_str(), &preferences)
         .await?;
+    if let Some(language) = response
+        .language
+        .as_deref()
+        .filter(|language| *language != SOURCE_LANGUAGE)
+    {
+        // The description can only be translated from English.
+        event!(Level::DEBUG, message = "Not translating the description.", %name, %language);
+        response.reason = Some(untranslatable_reason(language));
+        cache.insert(key, response.clone());
+        return Ok(response);
+    }
+    match translation_service
+        .attributed_translation(response.description.as_str())
+        .await
+    {
+        Ok(translated) => {
+            // A fallback translation is not cached, so the primary translator is tried again once
+            // it recovers.
+            let from_fallback = translation_service.is_fallback(&translated);
+            response.description = translated.text;
+            response.translated = true;
+            response.translator = translated.translator;
+            if !from_fallback {
+                cache.insert(key, response.clone());
+            }
+            Ok(response)
+        }
+        Err(error) if fallback && error.is_transient() => {
+            event!(Level::WARN, message = "Returning the untranslated description.", %name, %error);
+            response.reason = Some(error.to_string());
+            Ok(response)
+        }
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Look up each of the names in a batch, with at most `concurrency` lookups at a time. The results
+/// are in the order of the names. Any lookups that have not completed by the deadline (for the
+/// whole batch) are reported as timed out.
+async fn handle_batch_request<F, Fut>(
+    names: Vec<String>,
+    concurrency: usize,
+    deadline: Duration,
+    lookup: F,
+) -> Result<PokemonBatch, ServiceError>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<PokemonData, ServiceError>>,
+{
+    event!(
+        Level::INFO,
+        message = "Handling batch request.",
+        names = names.len()
+    );
+    if names.is_empty() {
+        return Err(ServiceError::EmptyBatch);
+    }
+    if names.len() > MAX_BATCH_NAMES {
+        return Err(ServiceError::BatchTooLarge(MAX_BATCH_NAMES));
+    }
+    let mut entries: Vec<Option<BatchEntry>> = vec![None; names.len()];
+    let lookups = names.clone().into_iter().enumerate().map(|(i, name)| {
+        let request = lookup(name.clone());
+        async move { (i, batch_entry(name, request.await)) }
+    });
+    let mut completed = futures::stream::iter(lookups).buffer_unordered(concurrency.max(1));
+    let all_completed = tokio::time::timeout(deadline, async {
+        while let Some((i, entry)) = completed.next().await {
+            entries[i] = Some(entry);
+        }
+    })
+    .await;
+    if all_completed.is_err() {
+        event!(Level::WARN, message = "Batch request deadline expired.");
+    }
+    let results = entries
+        .into_iter()
+        .zip(names)
+        .map(|(entry, name)| entry.unwrap_or_else(|| batch_entry(name, Err(ServiceError::Timeout))))
+        .collect();
+    Ok(PokemonBatch { results })
+}
+
+/// The entry in the response to a batch request for the result of looking up a name.
+fn batch_entry(name: String, result: Result<PokemonData, ServiceError>) -> BatchEntry {
+    match result {
+        Ok(pokemon) => BatchEntry {
+            name,
+            status: StatusCode::OK.as_u16(),
+            pokemon: Some(pokemon),
+            error: None,
+        },
+        Err(error) => BatchEntry {
+            name,
+            status: error.status_code().as_u16(),
+            pokemon: None,
+            error: Some(error.error_message()),
+        },
+    }
+}
+
+async fn handle_descriptions_request<Poke, Trans>(
+    name: String,
+    translator: Option<Translator<Trans>>,
+    pokemon_service: Arc<Poke>,
+    cache: Arc<DescriptionsCache>,
+) -> Result<PokemonDescriptions, ServiceError>
+where
+    Poke: DescriptionsService,
+    Trans: TranslationService,
+{
+    let style = translator
+        .as_ref()
+        .map(|translator| translator.style.as_str());
+    event!(Level::INFO, message = "Handling descriptions request.", %name, style);
+    let key = format!(
+        "{}?style={}",
+        to_slug(name.as_str()),
+        style.unwrap_or_default()
+    );
+    if let Some(response) = cache.get(&key) {
+        return Ok(response);
+    }
+    let mut response = pokemon_service.get_descriptions(name.as_str()).await?;
+
+    // Responses are only cached if every description that could be translated was, by the primary
+    // translator.
+    let mut complete = true;
+    if let Some(Translator {
+        service: translation_service,
+        ..
+    }) = &translator
+    {
+        // Once the translation service is rate limited, no more requests are made to it.
+        let mut rate_limited: Option<String> = None;
+        for description in response.descriptions.iter_mut() {
+            if description.language != SOURCE_LANGUAGE {
+                description.reason = Some(untranslatable_reason(description.language.as_str()));
+                continue;
+            }
+            if let Some(reason) = &rate_limited {
+                description.reason = Some(reason.clone());
+                continue;
+            }
+            match translation_service
+                .attributed_translation(description.description.as_str())
+                .await
+            {
+                Ok(translated) => {
+                    complete &= !translation_service.is_fallback(&translated);
+                    description.description = translated.text;
+                    description.translated = true;
+                    description.translator = translated.translator;
+                }
+                Err(error) => {
+                    event!(Level::WARN, message = "Returning an untranslated description.", %name, %error);
+                    complete = false;
+                    if let TranslationError::RateLimited(_) = error {
+                        rate_limited = Some(error.to_string());
+                    }
+                    description.reason = Some(error.to_string());
+                }
+            }
+        }
+    }
+    if complete {
+        cache.insert(key, response.clone());
+    }
     Ok(response)
 }
 
+/// The reason that a description in a language cannot be translated.
+fn untranslatable_reason(language: &str) -> String {
+    format!("Descriptions in \"{}\" cannot be translated.", language)
+}
+
 impl From<PokemonServiceError> for ServiceError {
     fn from(e: PokemonServiceError) -> Self {
         match e {
             PokemonServiceError::ServiceUnavailable => ServiceError::ServiceUnavailable,
-            PokemonServiceError::NoSuchPokemon(name) => ServiceError::NoSuchPokemon(name),
+            PokemonServiceError::NoSuchPokemon(name, suggestions) => {
+                ServiceError::NoSuchPokemon(name, suggestions)
+            }
+            PokemonServiceError::Timeout => ServiceError::Timeout,
         }
     }
 }
@@ -90,6 +708,10 @@ impl From<TranslationError> for ServiceError {
         match e {
             TranslationError::ServiceUnavailable => ServiceError::ServiceUnavailable,
             TranslationError::TranslationFailed => ServiceError::TranslationFailed,
+            TranslationError::Timeout => ServiceError::Timeout,
+            TranslationError::RateLimited(RateLimit { retry_after, .. }) => {
+                ServiceError::RateLimited(retry_after)
+            }
         }
     }
 }
@@ -97,28 +719,62 @@ impl From<TranslationError> for ServiceError {
 /// Combined error type for the service (convertible to an HTTP error response).
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum ServiceError {
-    /// No Pokemon of that name exists (404).
-    NoSuchPokemon(String),
+    /// No Pokemon of that name exists (404). Similar names may be suggested.
+    NoSuchPokemon(String, Vec<String>),
     /// The description could not be translated (500).
     TranslationFailed,
     /// One of the services could not provide a reply (503).
     ServiceUnavailable,
+    /// The translation service rate limit has been exceeded (429). Clients will be told to retry
+    /// after the specified duration, if it is known.
+    RateLimited(Option<Duration>),
+    /// One of the services did not respond in time or the request deadline expired (504).
+    Timeout,
+    /// The requested style of translation is not available (400). The available styles are
+    /// suggested.
+    UnknownStyle(String, Vec<String>),
+    /// A batch request contained more than the maximum number of names (400).
+    BatchTooLarge(usize),
+    /// A batch request did not contain any names (400).
+    EmptyBatch,
+    /// The body of the request could not be parsed (400).
+    InvalidBody(String),
+    /// The body of the request was larger than the limit, in bytes (413).
+    BodyTooLarge(u64),
 }
 
 impl ServiceError {
     fn status_code(&self) -> StatusCode {
         match self {
-            ServiceError::NoSuchPokemon(_) => StatusCode::NOT_FOUND,
+            ServiceError::NoSuchPokemon(..) => StatusCode::NOT_FOUND,
             ServiceError::TranslationFailed => StatusCode::INTERNAL_SERVER_ERROR,
             ServiceError::ServiceUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ServiceError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            ServiceError::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            ServiceError::UnknownStyle(..)
+            | ServiceError::BatchTooLarge(_)
+            | ServiceError::EmptyBatch
+            | ServiceError::InvalidBody(_) => StatusCode::BAD_REQUEST,
+            ServiceError::BodyTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
         }
     }
+
+    /// The body of the error response.
+    fn error_message(&self) -> ErrorMessage {
+        let mut msg = ErrorMessage::new(self.to_string());
+        if let ServiceError::NoSuchPokemon(_, suggestions)
+        | ServiceError::UnknownStyle(_, suggestions) = self
+        {
+            msg.suggestions = suggestions.clone();
+        }
+        msg
+    }
 }
 
 impl Display for ServiceError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            ServiceError::NoSuchPokemon(name) => {
+            ServiceError::NoSuchPokemon(name, _) => {
                 write!(f, "There is no Pokemon with name:  \"{}\".", name)
             }
             ServiceError::TranslationFailed => write!(
@@ -129,6 +785,26 @@ impl Display for ServiceError {
                 f,
                 "The Pokemon description translation service is currently unavailable."
             ),
+            ServiceError::RateLimited(_) => write!(
+                f,
+                "Too many requests have been made to the Pokemon description translation service."
+            ),
+            // The Pokemon service, the translation service or the deadline may be responsible.
+            ServiceError::Timeout => write!(f, "An upstream service did not respond in time."),
+            ServiceError::UnknownStyle(style, _) => {
+                write!(f, "There is no translation style named \"{}\".", style)
+            }
+            ServiceError::BatchTooLarge(max) => {
+                write!(f, "A batch request may contain at most {} names.", max)
+            }
+            ServiceError::EmptyBatch => write!(
+                f,
+                "The names of the Pokemon must be given, in the \"names\" query parameter or the \"names\" field of the request body."
+            ),
+            ServiceError::InvalidBody(error) => write!(f, "The request body is invalid: {}", error),
+            ServiceError::BodyTooLarge(max) => {
+                write!(f, "The request body may be at most {} bytes.", max)
+            }
         }
     }
 }
@@ -141,18 +817,51 @@ impl Reply for PokemonData {
     }
 }
 
+impl Reply for PokemonDescriptions {
+    fn into_response(self) -> Response {
+        warp::reply::json(&self).into_response()
+    }
+}
+
+impl Reply for PokemonBatch {
+    fn into_response(self) -> Response {
+        warp::reply::json(&self).into_response()
+    }
+}
+
+impl Reply for ServiceStatus {
+    fn into_response(self) -> Response {
+        warp::reply::json(&self).into_response()
+    }
+}
+
 impl Reject for ServiceError {}
 
 impl Reply for ServiceError {
     fn into_response(self) -> Response {
-        let msg = ErrorMessage::new(self.to_string());
-        warp::reply::with_status(warp::reply::json(&msg), self.status_code()).into_response()
+        let msg = self.error_message();
+        let response =
+            warp::reply::with_status(warp::reply::json(&msg), self.status_code()).into_response();
+        if let ServiceError::RateLimited(Some(retry_after)) = self {
+            // Round up so clients do not retry before the rate limit window has expired.
+            let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            warp::reply::with_header(response, RETRY_AFTER, secs.to_string()).into_response()
+        } else {
+            response
+        }
     }
 }
 
 async fn handle_rejection(rejection: Rejection) -> Result<impl Reply, Rejection> {
     if let Some(e) = rejection.find::<ServiceError>() {
         Ok(e.clone())
+    } else if let Some(e) = rejection.find::<BodyDeserializeError>() {
+        Ok(ServiceError::InvalidBody(
+            e.source()
+                .map_or_else(|| e.to_string(), ToString::to_string),
+        ))
+    } else if rejection.find::<PayloadTooLarge>().is_some() {
+        Ok(ServiceError::BodyTooLarge(MAX_BATCH_BODY))
     } else {
         Err(rejection)
     }
@@ -1,12 +1,23 @@
-use crate::endpoints::ServiceError;
-use crate::model::PokemonData;
-use crate::services::pokemon::{PokemonService, PokemonServiceError};
-use crate::services::translation::{TranslationError, TranslationService};
-use futures::future::{ready, BoxFuture};
+use crate::chain::TranslatorChain;
+use crate::circuit_breaker::{BreakerConfig, CircuitBreaker, CircuitState, CircuitStatus};
+use crate::endpoints::{
+    DescriptionsCache, EndpointSettings, ResponseCache, ResponseCaches, ServiceError,
+};
+use crate::model::{Description, PokemonBatch, PokemonData, PokemonDescriptions, ServiceStatus};
+use crate::services::pokemon::{
+    DescriptionPreferences, DescriptionsService, PokemonService, PokemonServiceError,
+};
+use crate::services::translation::{
+    RateLimit, TranslationError, TranslationService, Translator, TranslatorRegistry,
+};
+use futures::future::{pending, ready, BoxFuture};
 use futures::FutureExt;
 use reqwest::StatusCode;
 use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use warp::{Rejection, Reply};
 
 enum FakePokeService {
@@ -19,10 +30,7 @@ impl FakePokeService {
         let mut map = HashMap::new();
         map.insert(
             name.to_string(),
-            PokemonData {
-                name: name.to_string(),
-                description: description.to_string(),
-            },
+            PokemonData::new(name.to_string(), description.to_string()),
         );
         FakePokeService::Containing(map)
     }
@@ -32,6 +40,7 @@ impl PokemonService for FakePokeService {
     fn get_pokemon<'a>(
         &'a self,
         name: &'a str,
+        _preferences: &'a DescriptionPreferences,
     ) -> BoxFuture<'a, Result<PokemonData, PokemonServiceError>> {
         ready(match self {
             FakePokeService::Unavailable => Err(PokemonServiceError::ServiceUnavailable),
@@ -39,7 +48,7 @@ impl PokemonService for FakePokeService {
                 if let Some(data) = map.get(name) {
                     Ok(data.clone())
                 } else {
-                    Err(PokemonServiceError::NoSuchPokemon(name.to_string()))
+                    Err(PokemonServiceError::no_such_pokemon(name))
                 }
             }
         })
@@ -51,6 +60,7 @@ enum FakeTranslationService {
     Unavailable,
     Fail,
     Succeed,
+    Hang,
 }
 
 impl TranslationService for FakeTranslationService {
@@ -58,12 +68,51 @@ impl TranslationService for FakeTranslationService {
         &'a self,
         text: &'a str,
     ) -> BoxFuture<'a, Result<String, TranslationError>> {
-        ready(match self {
-            FakeTranslationService::Unavailable => Err(TranslationError::ServiceUnavailable),
-            FakeTranslationService::Fail => Err(TranslationError::TranslationFailed),
-            FakeTranslationService::Succeed => Ok(text.to_uppercase()),
-        })
-        .boxed()
+        match self {
+            FakeTranslationService::Unavailable => {
+                ready(Err(TranslationError::ServiceUnavailable)).boxed()
+            }
+            FakeTranslationService::Fail => ready(Err(TranslationError::TranslationFailed)).boxed(),
+            FakeTranslationService::Succeed => ready(Ok(text.to_uppercase())).boxed(),
+            FakeTranslationService::Hang => pending().boxed(),
+        }
+    }
+}
+
+const CAPACITY: usize = 8;
+const TTL: Duration = Duration::from_secs(60);
+const SETTINGS: EndpointSettings = EndpointSettings {
+    fallback: false,
+    deadline: Duration::from_secs(5),
+    batch_concurrency: 2,
+    preferences: DescriptionPreferences {
+        versions: Vec::new(),
+        languages: Vec::new(),
+    },
+};
+
+fn make_cache() -> Arc<ResponseCache> {
+    Arc::new(ResponseCache::new(
+        NonZeroUsize::new(CAPACITY).unwrap(),
+        TTL,
+    ))
+}
+
+fn shakespeare<T>(service: Arc<T>) -> Translator<T> {
+    Translator {
+        style: "shakespeare".to_string(),
+        service,
+    }
+}
+
+fn registry<T>(service: T) -> TranslatorRegistry<T> {
+    TranslatorRegistry::new("shakespeare", service)
+}
+
+fn translated(name: &str, description: &str) -> PokemonData {
+    PokemonData {
+        translated: true,
+        ..PokemonData::new(name.to_string(), description.to_string())
     }
 }
 
@@ -72,23 +121,155 @@ async fn combined_services_nominal() {
     let poke_service = Arc::new(FakePokeService::with("name", "A description."));
     let trans_service = Arc::new(FakeTranslationService::Succeed);
 
-    let result = super::handle_request("name".to_string(), poke_service, trans_service).await;
+    let result = super::handle_request(
+        "name".to_string(),
+        DescriptionPreferences::default(),
+        false,
+        poke_service,
+        shakespeare(trans_service),
+        make_cache(),
+    )
+    .await;
+
+    assert_eq!(result, Ok(translated("name", "A DESCRIPTION.")));
+}
+
+#[tokio::test]
+async fn translator_reported() {
+    let poke_service = Arc::new(FakePokeService::with("name", "A description."));
+    let chain = TranslatorChain::default()
+        .then(
+            "first",
+            FakeTranslationService::Unavailable,
+            TranslationError::is_transient,
+        )
+        .then(
+            "second",
+            FakeTranslationService::Succeed,
+            TranslationError::is_transient,
+        );
+
+    let result = super::handle_request(
+        "name".to_string(),
+        DescriptionPreferences::default(),
+        false,
+        poke_service,
+        shakespeare(Arc::new(chain)),
+        make_cache(),
+    )
+    .await;
 
     assert_eq!(
         result,
         Ok(PokemonData {
-            name: "name".to_string(),
-            description: "A DESCRIPTION.".to_string()
+            translator: Some("second".to_string()),
+            ..translated("name", "A DESCRIPTION.")
         })
     );
 }
 
+fn fallback_chain<T>(primary: T) -> TranslatorChain
+where
+    T: TranslationService + Send + Sync + 'static,
+{
+    TranslatorChain::default()
+        .then("first", primary, TranslationError::is_transient)
+        .then(
+            "second",
+            FakeTranslationService::Succeed,
+            TranslationError::is_transient,
+        )
+}
+
+#[tokio::test]
+async fn fallback_translation_not_cached() {
+    let poke_service = Arc::new(FakePokeService::with("name", "A description."));
+    let cache = make_cache();
+
+    let result = super::handle_request(
+        "name".to_string(),
+        DescriptionPreferences::default(),
+        false,
+        poke_service.clone(),
+        shakespeare(Arc::new(fallback_chain(
+            FakeTranslationService::Unavailable,
+        ))),
+        cache.clone(),
+    )
+    .await;
+    assert_eq!(result.unwrap().translator, Some("second".to_string()));
+
+    // The primary translator is tried again and its translation is cached.
+    let primary = Arc::new(fallback_chain(FakeTranslationService::Succeed));
+    let result = super::handle_request(
+        "name".to_string(),
+        DescriptionPreferences::default(),
+        false,
+        poke_service.clone(),
+        shakespeare(primary),
+        cache.clone(),
+    )
+    .await;
+    assert_eq!(result.unwrap().translator, Some("first".to_string()));
+
+    let cached = super::handle_request(
+        "name".to_string(),
+        DescriptionPreferences::default(),
+        false,
+        poke_service,
+        shakespeare(Arc::new(FakeTranslationService::Fail)),
+        cache,
+    )
+    .await;
+    assert_eq!(cached.unwrap().translator, Some("first".to_string()));
+}
+
+#[tokio::test]
+async fn cached_response_skips_services() {
+    let cache = make_cache();
+    let expected = translated("name", "A DESCRIPTION.");
+
+    let poke_service = Arc::new(FakePokeService::with("name", "A description."));
+    let trans_service = Arc::new(FakeTranslationService::Succeed);
+    let first = super::handle_request(
+        "name".to_string(),
+        DescriptionPreferences::default(),
+        false,
+        poke_service,
+        shakespeare(trans_service),
+        cache.clone(),
+    )
+    .await;
+    assert_eq!(first, Ok(expected.clone()));
+
+    let poke_service = Arc::new(FakePokeService::Unavailable);
+    let trans_service = Arc::new(FakeTranslationService::Unavailable);
+    let second = super::handle_request(
+        "Name".to_string(),
+        DescriptionPreferences::default(),
+        false,
+        poke_service,
+        shakespeare(trans_service),
+        cache,
+    )
+    .await;
+    assert_eq!(second, Ok(expected));
+}
+
 #[tokio::test]
 async fn pokemon_service_unavailable() {
     let poke_service = Arc::new(FakePokeService::Unavailable);
     let trans_service = Arc::new(FakeTranslationService::Succeed);
 
-    let result = super::handle_request("name".to_string(), poke_service, trans_service).await;
+    let result = super::handle_request(
+        "name".to_string(),
+        DescriptionPreferences::default(),
+        false,
+        poke_service,
+        shakespeare(trans_service),
+        make_cache(),
+    )
+    .await;
 
     assert_eq!(result, Err(ServiceError::ServiceUnavailable));
 }
@@ -98,17 +279,90 @@ async fn translations_unavailable() {
     let poke_service = Arc::new(FakePokeService::with("name", "A description."));
     let trans_service = Arc::new(FakeTranslationService::Unavailable);
 
-    let result = super::handle_request("name".to_string(), poke_service, trans_service).await;
+    let result = super::handle_request(
+        "name".to_string(),
+        DescriptionPreferences::default(),
+        false,
+        poke_service,
+        shakespeare(trans_service),
+        make_cache(),
+    )
+    .await;
 
     assert_eq!(result, Err(ServiceError::ServiceUnavailable));
 }
 
+#[tokio::test]
+async fn translations_unavailable_fallback() {
+    let cache = make_cache();
+    let poke_service = Arc::new(FakePokeService::with("name", "A description."));
+    let trans_service = Arc::new(FakeTranslationService::Unavailable);
+
+    let result = super::handle_request(
+        "name".to_string(),
+        DescriptionPreferences::default(),
+        true,
+        poke_service.clone(),
+        shakespeare(trans_service),
+        cache.clone(),
+    )
+    .await;
+
+    assert_eq!(
+        result,
+        Ok(PokemonData {
+            reason: Some(TranslationError::ServiceUnavailable.to_string()),
+            ..PokemonData::new("name".to_string(), "A description.".to_string())
+        })
+    );
+
+    // The untranslated description should not be cached.
+    let trans_service = Arc::new(FakeTranslationService::Succeed);
+    let result = super::handle_request(
+        "name".to_string(),
+        DescriptionPreferences::default(),
+        true,
+        poke_service,
+        shakespeare(trans_service),
+        cache,
+    )
+    .await;
+
+    assert_eq!(result, Ok(translated("name", "A DESCRIPTION.")));
+}
+
+#[tokio::test]
+async fn translation_failed_no_fallback() {
+    let poke_service = Arc::new(FakePokeService::with("name", "A description."));
+    let trans_service = Arc::new(FakeTranslationService::Fail);
+
+    let result = super::handle_request(
+        "name".to_string(),
+        DescriptionPreferences::default(),
+        true,
+        poke_service,
+        shakespeare(trans_service),
+        make_cache(),
+    )
+    .await;
+
+    assert_eq!(result, Err(ServiceError::TranslationFailed));
+}
+
 #[tokio::test]
 async fn translation_failed() {
     let poke_service = Arc::new(FakePokeService::with("name", "A description."));
     let trans_service = Arc::new(FakeTranslationService::Fail);
 
-    let result = super::handle_request("name".to_string(), poke_service, trans_service).await;
+    let result = super::handle_request(
+        "name".to_string(),
+        DescriptionPreferences::default(),
+        false,
+        poke_service,
+        shakespeare(trans_service),
+        make_cache(),
+    )
+    .await;
 
     assert_eq!(result, Err(ServiceError::TranslationFailed));
 }
@@ -118,11 +372,19 @@ async fn pokemon_not_found() {
     let poke_service = Arc::new(FakePokeService::with("name", "A description."));
     let trans_service = Arc::new(FakeTranslationService::Succeed);
 
-    let result = super::handle_request("other".to_string(), poke_service, trans_service).await;
+    let result = super::handle_request(
+        "other".to_string(),
+        DescriptionPreferences::default(),
+        false,
+        poke_service,
+        shakespeare(trans_service),
+        make_cache(),
+    )
+    .await;
 
     assert_eq!(
         result,
-        Err(ServiceError::NoSuchPokemon("other".to_string()))
+        Err(ServiceError::NoSuchPokemon("other".to_string(), vec![]))
     );
 }
 
@@ -131,7 +393,12 @@ async fn filter_good_request() {
     let poke_service = FakePokeService::with("name", "A description.");
     let trans_service = FakeTranslationService::Succeed;
 
-    let filter = super::make_endpoint_filter(poke_service, trans_service);
+    let filter = super::make_endpoint_filter(
+        Arc::new(poke_service),
+        Arc::new(registry(trans_service)),
+        make_cache(),
+        SETTINGS,
+    );
     let result = warp::test::request()
         .path("/pokemon/name")
         .filter(&filter)
@@ -139,13 +406,197 @@ async fn filter_good_request() {
 
     assert!(result.is_ok());
     let data = result.unwrap();
+    assert_eq!(data, translated("name", "A DESCRIPTION."));
+}
+
+#[tokio::test]
+async fn filter_escaped_name() {
+    let poke_service = FakePokeService::with("#025", "A description.");
+    let trans_service = FakeTranslationService::Succeed;
+
+    let filter = super::make_endpoint_filter(
+        Arc::new(poke_service),
+        Arc::new(registry(trans_service)),
+        make_cache(),
+        SETTINGS,
+    );
+    let result = warp::test::request()
+        .path("/pokemon/%23025")
+        .filter(&filter)
+        .await;
+
+    assert_eq!(result.unwrap(), translated("#025", "A DESCRIPTION."));
+}
+
+#[tokio::test]
+async fn filter_fallback_option() {
+    let poke_service = FakePokeService::with("name", "A description.");
+    let trans_service = FakeTranslationService::Unavailable;
+
+    let filter = super::make_endpoint_filter(
+        Arc::new(poke_service),
+        Arc::new(registry(trans_service)),
+        make_cache(),
+        SETTINGS,
+    );
+
+    let result = warp::test::request()
+        .path("/pokemon/name")
+        .filter(&filter)
+        .await;
+    let err = result.err().unwrap();
+    assert_eq!(
+        err.find::<ServiceError>(),
+        Some(&ServiceError::ServiceUnavailable)
+    );
+
+    let result = warp::test::request()
+        .path("/pokemon/name?fallback=true")
+        .filter(&filter)
+        .await;
+    let data = result.unwrap();
+    assert_eq!(data.description, "A description.");
+    assert!(!data.translated);
+}
+
+#[tokio::test]
+async fn filter_deadline_expired() {
+    let poke_service = FakePokeService::with("name", "A description.");
+    let trans_service = FakeTranslationService::Hang;
+
+    let filter = super::make_endpoint_filter(
+        Arc::new(poke_service),
+        Arc::new(registry(trans_service)),
+        make_cache(),
+        EndpointSettings {
+            deadline: Duration::from_millis(10),
+            ..SETTINGS
+        },
+    );
+    let result = warp::test::request()
+        .path("/pokemon/name")
+        .filter(&filter)
+        .await;
+
+    let err = result.err().unwrap();
+    assert_eq!(err.find::<ServiceError>(), Some(&ServiceError::Timeout));
+}
+
+/// Fake service that describes every Pokemon by the preferred versions of the game.
+struct VersionsPokeService;
+
+impl PokemonService for VersionsPokeService {
+    fn get_pokemon<'a>(
+        &'a self,
+        name: &'a str,
+        preferences: &'a DescriptionPreferences,
+    ) -> BoxFuture<'a, Result<PokemonData, PokemonServiceError>> {
+        ready(Ok(PokemonData::new(
+            name.to_string(),
+            preferences.versions.join(","),
+        )))
+        .boxed()
+    }
+}
+
+#[tokio::test]
+async fn filter_version_option() {
+    let filter = super::make_endpoint_filter(
+        Arc::new(VersionsPokeService),
+        Arc::new(registry(FakeTranslationService::Succeed)),
+        make_cache(),
+        EndpointSettings {
+            preferences: DescriptionPreferences {
+                versions: vec!["red".to_string()],
+                languages: vec![],
+            },
+            ..SETTINGS
+        },
+    );
+
+    let result = warp::test::request()
+        .path("/pokemon/name")
+        .filter(&filter)
+        .await;
+    assert_eq!(result.unwrap().description, "RED");
+
+    // Responses for different versions are cached separately.
+    let result = warp::test::request()
+        .path("/pokemon/name?version=Sword,%20shield")
+        .filter(&filter)
+        .await;
+    assert_eq!(result.unwrap().description, "SWORD,SHIELD");
+}
+
+#[test]
+fn accept_language() {
     assert_eq!(
-        data,
+        super::parse_accept_language("fr-CH, fr;q=0.9, en;q=0.8, de;q=0.7, *;q=0.5"),
+        vec!["fr-ch", "fr", "en", "de"]
+    );
+    assert_eq!(
+        super::parse_accept_language("en;q=0.5, ja, de;q=0"),
+        vec!["ja", "en"]
+    );
+    assert!(super::parse_accept_language("").is_empty());
+}
+
+#[test]
+fn requested_languages_take_precedence() {
+    let defaults = DescriptionPreferences {
+        versions: vec!["red".to_string()],
+        languages: vec!["fr".to_string(), "en".to_string()],
+    };
+    let header = Some("de, en;q=0.5".to_string());
+
+    let preferences = super::request_preferences(
+        defaults.clone(),
+        super::RequestOptions::default(),
+        header.clone(),
+    );
+    assert_eq!(preferences.versions, vec!["red"]);
+    assert_eq!(preferences.languages, vec!["de", "en", "fr"]);
+
+    // The query parameter overrides the header.
+    let options = super::RequestOptions {
+        language: Some("ja".to_string()),
+        ..Default::default()
+    };
+    let preferences = super::request_preferences(defaults.clone(), options, header);
+    assert_eq!(preferences.languages, vec!["ja", "fr", "en"]);
+
+    let preferences = super::request_preferences(defaults, super::RequestOptions::default(), None);
+    assert_eq!(preferences.languages, vec!["fr", "en"]);
+}
+
+#[tokio::test]
+async fn other_languages_not_translated() {
+    let mut map = HashMap::new();
+    map.insert(
+        "name".to_string(),
         PokemonData {
-            name: "name".to_string(),
-            description: "A DESCRIPTION.".to_string()
-        }
+            language: Some("de".to_string()),
+            ..PokemonData::new("name".to_string(), "Eine Beschreibung.".to_string())
+        },
     );
+    let poke_service = Arc::new(FakePokeService::Containing(map));
+    // Any attempt to translate the description would fail.
+    let trans_service = Arc::new(FakeTranslationService::Fail);
+
+    let result = super::handle_request(
+        "name".to_string(),
+        DescriptionPreferences::default(),
+        false,
+        poke_service,
+        shakespeare(trans_service),
+        make_cache(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(result.description, "Eine Beschreibung.");
+    assert!(!result.translated);
+    assert!(result.reason.is_some());
 }
 
 #[tokio::test]
@@ -153,7 +604,12 @@ async fn filter_bad_request() {
     let poke_service = FakePokeService::with("name", "A description.");
     let trans_service = FakeTranslationService::Succeed;
 
-    let filter = super::make_endpoint_filter(poke_service, trans_service);
+    let filter = super::make_endpoint_filter(
+        Arc::new(poke_service),
+        Arc::new(registry(trans_service)),
+        make_cache(),
+        SETTINGS,
+    );
     let result = warp::test::request()
         .path("/pokemon/other")
         .filter(&filter)
@@ -163,7 +619,395 @@ async fn filter_bad_request() {
     let reject: Rejection = result.err().unwrap();
 
     let err = reject.find::<ServiceError>();
-    assert_eq!(err, Some(&ServiceError::NoSuchPokemon("other".to_string())));
+    assert_eq!(
+        err,
+        Some(&ServiceError::NoSuchPokemon("other".to_string(), vec![]))
+    );
+}
+
+#[tokio::test]
+async fn status_filter() {
+    let breaker = CircuitBreaker::new(
+        FakeTranslationService::Succeed,
+        "translation",
+        BreakerConfig {
+            failure_threshold: 1,
+            cool_down: Duration::from_secs(60),
+        },
+    );
+
+    let filter = super::make_status_filter(vec![breaker.circuit()]);
+    let result = warp::test::request().path("/status").filter(&filter).await;
+
+    assert_eq!(
+        result.unwrap(),
+        ServiceStatus {
+            circuits: vec![CircuitStatus {
+                name: "translation".to_string(),
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+            }]
+        }
+    );
+}
+
+#[tokio::test]
+async fn styles_cached_separately() {
+    let poke_service = Arc::new(FakePokeService::with("name", "A description."));
+    let cache = make_cache();
+
+    let first = super::handle_request(
+        "name".to_string(),
+        DescriptionPreferences::default(),
+        false,
+        poke_service.clone(),
+        shakespeare(Arc::new(FakeTranslationService::Succeed)),
+        cache.clone(),
+    )
+    .await;
+    assert!(first.is_ok());
+
+    let yoda = Translator {
+        style: "yoda".to_string(),
+        service: Arc::new(FakeTranslationService::Fail),
+    };
+    let second = super::handle_request(
+        "name".to_string(),
+        DescriptionPreferences::default(),
+        false,
+        poke_service,
+        yoda,
+        cache,
+    )
+    .await;
+    assert_eq!(second, Err(ServiceError::TranslationFailed));
+}
+
+#[tokio::test]
+async fn filter_style_option() {
+    let mut translators = registry(FakeTranslationService::Succeed);
+    translators.register("yoda", FakeTranslationService::Fail);
+
+    let filter = super::make_endpoint_filter(
+        Arc::new(FakePokeService::with("name", "A description.")),
+        Arc::new(translators),
+        make_cache(),
+        SETTINGS,
+    );
+
+    let result = warp::test::request()
+        .path("/pokemon/name")
+        .filter(&filter)
+        .await;
+    assert_eq!(result.ok(), Some(translated("name", "A DESCRIPTION.")));
+
+    let result = warp::test::request()
+        .path("/pokemon/name?style=Yoda")
+        .filter(&filter)
+        .await;
+    assert_eq!(
+        result.err().unwrap().find::<ServiceError>(),
+        Some(&ServiceError::TranslationFailed)
+    );
+
+    let result = warp::test::request()
+        .path("/pokemon/name?style=klingon")
+        .filter(&filter)
+        .await;
+    assert_eq!(
+        result.err().unwrap().find::<ServiceError>(),
+        Some(&ServiceError::UnknownStyle(
+            "klingon".to_string(),
+            vec!["shakespeare".to_string(), "yoda".to_string()]
+        ))
+    );
+}
+
+fn statuses(batch: &PokemonBatch) -> Vec<(&str, u16)> {
+    batch
+        .results
+        .iter()
+        .map(|entry| (entry.name.as_str(), entry.status))
+        .collect()
+}
+
+#[tokio::test]
+async fn filter_batch_body() {
+    let filter = super::make_batch_filter(
+        Arc::new(FakePokeService::with("name", "A description.")),
+        Arc::new(registry(FakeTranslationService::Succeed)),
+        make_cache(),
+        SETTINGS,
+    );
+
+    let batch = warp::test::request()
+        .method("POST")
+        .path("/pokemon/batch")
+        .json(&serde_json::json!({ "names": ["name", "other", "name"] }))
+        .filter(&filter)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        statuses(&batch),
+        vec![("name", 200), ("other", 404), ("name", 200)]
+    );
+    assert_eq!(
+        batch.results[0].pokemon,
+        Some(translated("name", "A DESCRIPTION."))
+    );
+    assert!(batch.results[0].error.is_none());
+    assert!(batch.results[1].pokemon.is_none());
+    assert_eq!(
+        batch.results[1].error,
+        Some(ServiceError::NoSuchPokemon("other".to_string(), vec![]).error_message())
+    );
+}
+
+#[tokio::test]
+async fn filter_batch_query() {
+    let filter = super::make_batch_filter(
+        Arc::new(FakePokeService::with("name", "A description.")),
+        Arc::new(registry(FakeTranslationService::Unavailable)),
+        make_cache(),
+        SETTINGS,
+    );
+
+    let batch = warp::test::request()
+        .path("/pokemon?names=other,%20name,,&fallback=true")
+        .filter(&filter)
+        .await
+        .unwrap();
+
+    assert_eq!(statuses(&batch), vec![("other", 404), ("name", 200)]);
+    let pokemon = batch.results[1].pokemon.as_ref().unwrap();
+    assert_eq!(pokemon.description, "A description.");
+    assert!(!pokemon.translated);
+
+    let result = warp::test::request()
+        .path("/pokemon?names=name&style=klingon")
+        .filter(&filter)
+        .await;
+    assert_eq!(
+        result.err().unwrap().find::<ServiceError>(),
+        Some(&ServiceError::UnknownStyle(
+            "klingon".to_string(),
+            vec!["shakespeare".to_string()]
+        ))
+    );
+}
+
+#[tokio::test]
+async fn batch_too_large() {
+    let names = (0..=super::MAX_BATCH_NAMES)
+        .map(|i| format!("name{}", i))
+        .collect::<Vec<_>>();
+    let lookups = Arc::new(AtomicUsize::new(0));
+
+    let result = super::handle_batch_request(names, 2, Duration::from_secs(5), |name| {
+        lookups.fetch_add(1, Ordering::SeqCst);
+        ready(Ok(PokemonData::new(name, String::new())))
+    })
+    .await;
+
+    assert_eq!(
+        result,
+        Err(ServiceError::BatchTooLarge(super::MAX_BATCH_NAMES))
+    );
+    assert_eq!(lookups.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn batch_concurrency_limited() {
+    let running = Arc::new(AtomicUsize::new(0));
+    let most_running = Arc::new(AtomicUsize::new(0));
+    let names = (0..6).map(|i| format!("name{}", i)).collect::<Vec<_>>();
+
+    let result = super::handle_batch_request(names, 2, Duration::from_secs(5), |name| {
+        let running = running.clone();
+        let most_running = most_running.clone();
+        async move {
+            let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+            most_running.fetch_max(now_running, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+            Ok(PokemonData::new(name, String::new()))
+        }
+    })
+    .await
+    .unwrap();
+
+    let names = result
+        .results
+        .iter()
+        .map(|entry| entry.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec!["name0", "name1", "name2", "name3", "name4", "name5"]
+    );
+    assert_eq!(most_running.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn batch_deadline() {
+    let names = vec!["fast", "slow", "waiting"]
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+
+    let started = std::time::Instant::now();
+    let result =
+        super::handle_batch_request(names, 1, Duration::from_millis(50), |name| async move {
+            if name == "slow" {
+                pending::<()>().await;
+            }
+            Ok(PokemonData::new(name, String::new()))
+        })
+        .await
+        .unwrap();
+
+    // The slow lookup holds up the last, which is never started, but the whole batch is completed
+    // by the deadline.
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(
+        statuses(&result),
+        vec![("fast", 200), ("slow", 504), ("waiting", 504)]
+    );
+    assert_eq!(
+        result.results[2].error,
+        Some(ServiceError::Timeout.error_message())
+    );
+}
+
+#[tokio::test]
+async fn batch_shares_response_cache() {
+    let cache = make_cache();
+    let batch_filter = super::make_batch_filter(
+        Arc::new(FakePokeService::with("name", "A description.")),
+        Arc::new(registry(FakeTranslationService::Succeed)),
+        cache.clone(),
+        SETTINGS,
+    );
+    // The services for single requests would fail, so the response must come from the cache.
+    let single_filter = super::make_endpoint_filter(
+        Arc::new(FakePokeService::Unavailable),
+        Arc::new(registry(FakeTranslationService::Fail)),
+        cache,
+        SETTINGS,
+    );
+
+    let batch = warp::test::request()
+        .path("/pokemon?names=name")
+        .filter(&batch_filter)
+        .await
+        .unwrap();
+    assert_eq!(statuses(&batch), vec![("name", 200)]);
+
+    let result = warp::test::request()
+        .path("/pokemon/name")
+        .filter(&single_filter)
+        .await;
+    assert_eq!(result.ok(), Some(translated("name", "A DESCRIPTION.")));
+}
+
+#[tokio::test]
+async fn filter_batch_missing_names() {
+    let filter = super::make_batch_filter(
+        Arc::new(FakePokeService::with("name", "A description.")),
+        Arc::new(registry(FakeTranslationService::Succeed)),
+        make_cache(),
+        SETTINGS,
+    );
+
+    for path in &[
+        "/pokemon",
+        "/pokemon?names=",
+        "/pokemon?names=,%20,&fallback=true",
+    ] {
+        let result = warp::test::request().path(path).filter(&filter).await;
+        assert_eq!(
+            result.err().unwrap().find::<ServiceError>(),
+            Some(&ServiceError::EmptyBatch),
+            "{}",
+            path
+        );
+    }
+}
+
+/// Fake service that counts the requests for any Pokemon (none of which exist).
+#[derive(Default)]
+struct CountingPokeService {
+    calls: Arc<AtomicUsize>,
+}
+
+impl PokemonService for CountingPokeService {
+    fn get_pokemon<'a>(
+        &'a self,
+        name: &'a str,
+        _preferences: &'a DescriptionPreferences,
+    ) -> BoxFuture<'a, Result<PokemonData, PokemonServiceError>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        ready(Err(PokemonServiceError::no_such_pokemon(name))).boxed()
+    }
+}
+
+impl DescriptionsService for CountingPokeService {
+    fn get_descriptions<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<PokemonDescriptions, PokemonServiceError>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        ready(Err(PokemonServiceError::no_such_pokemon(name))).boxed()
+    }
+}
+
+#[tokio::test]
+async fn routes_reject_bad_batch_requests() {
+    let pokemon = CountingPokeService::default();
+    let calls = pokemon.calls.clone();
+    let routes = super::make_routes(
+        pokemon,
+        registry(FakeTranslationService::Succeed),
+        ResponseCaches {
+            pokemon: ResponseCache::new(NonZeroUsize::new(CAPACITY).unwrap(), TTL),
+            descriptions: DescriptionsCache::new(NonZeroUsize::new(CAPACITY).unwrap(), TTL),
+        },
+        vec![],
+        SETTINGS,
+    );
+
+    let response = warp::test::request()
+        .method("POST")
+        .path("/pokemon/batch")
+        .header("Content-Type", "application/json")
+        .body("{\"names\": [")
+        .reply(&routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = warp::test::request()
+        .method("POST")
+        .path("/pokemon/batch")
+        .json(&serde_json::json!({ "names": vec!["pikachu"; 2000] }))
+        .reply(&routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let response = warp::test::request().path("/pokemon").reply(&routes).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let json: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert!(json["message"].as_str().unwrap().contains("\"names\""));
+
+    // None of the rejected requests is taken as a request for a Pokemon named "batch".
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    let response = warp::test::request()
+        .path("/pokemon/batch")
+        .reply(&routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
 }
 
 #[test]
@@ -177,9 +1021,305 @@ fn error_http_status_codes() {
         StatusCode::INTERNAL_SERVER_ERROR
     );
     assert_eq!(
-        ServiceError::NoSuchPokemon("name".to_string())
+        ServiceError::NoSuchPokemon("name".to_string(), vec![])
             .into_response()
             .status(),
         StatusCode::NOT_FOUND
     );
+    assert_eq!(
+        ServiceError::RateLimited(None).into_response().status(),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+    assert_eq!(
+        ServiceError::Timeout.into_response().status(),
+        StatusCode::GATEWAY_TIMEOUT
+    );
+    assert_eq!(
+        ServiceError::UnknownStyle("klingon".to_string(), vec![])
+            .into_response()
+            .status(),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        ServiceError::BatchTooLarge(100).into_response().status(),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        ServiceError::EmptyBatch.into_response().status(),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        ServiceError::InvalidBody("EOF".to_string())
+            .into_response()
+            .status(),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        ServiceError::BodyTooLarge(1024).into_response().status(),
+        StatusCode::PAYLOAD_TOO_LARGE
+    );
+}
+
+struct TimeoutPokeService;
+
+impl PokemonService for TimeoutPokeService {
+    fn get_pokemon<'a>(
+        &'a self,
+        _name: &'a str,
+        _preferences: &'a DescriptionPreferences,
+    ) -> BoxFuture<'a, Result<PokemonData, PokemonServiceError>> {
+        ready(Err(PokemonServiceError::Timeout)).boxed()
+    }
+}
+
+#[tokio::test]
+async fn pokemon_service_timeout() {
+    let result = super::handle_request(
+        "name".to_string(),
+        DescriptionPreferences::default(),
+        false,
+        Arc::new(TimeoutPokeService),
+        shakespeare(Arc::new(FakeTranslationService::Succeed)),
+        make_cache(),
+    )
+    .await;
+
+    let error = result.unwrap_err();
+    assert_eq!(error, ServiceError::Timeout);
+    assert_eq!(
+        error.error_message().message,
+        "An upstream service did not respond in time."
+    );
+}
+
+#[test]
+fn rate_limited_retry_after() {
+    let response = ServiceError::RateLimited(Some(Duration::from_millis(59500))).into_response();
+    assert_eq!(response.headers().get("Retry-After").unwrap(), "60");
+
+    let response = ServiceError::RateLimited(None).into_response();
+    assert!(response.headers().get("Retry-After").is_none());
+}
+
+#[tokio::test]
+async fn suggestions_in_not_found_body() {
+    let response = ServiceError::NoSuchPokemon(
+        "pikachoo".to_string(),
+        vec!["pikachu".to_string(), "pichu".to_string()],
+    )
+    .into_response();
+    let body = warp::hyper::body::to_bytes(response.into_body())
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["suggestions"], serde_json::json!(["pikachu", "pichu"]));
+
+    let response = ServiceError::NoSuchPokemon("pikachoo".to_string(), vec![]).into_response();
+    let body = warp::hyper::body::to_bytes(response.into_body())
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert!(json.get("suggestions").is_none());
+}
+
+/// Fake service with two English descriptions and one German description of every Pokemon.
+struct FakeDescriptionsService;
+
+fn description(language: &str, versions: &[&str], text: &str) -> Description {
+    Description {
+        language: language.to_string(),
+        versions: versions.iter().map(|version| version.to_string()).collect(),
+        description: text.to_string(),
+        translated: false,
+        translator: None,
+        reason: None,
+    }
+}
+
+impl DescriptionsService for FakeDescriptionsService {
+    fn get_descriptions<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<PokemonDescriptions, PokemonServiceError>> {
+        ready(Ok(PokemonDescriptions {
+            name: name.to_string(),
+            variety: None,
+            descriptions: vec![
+                description("en", &["red", "blue"], "First."),
+                description("de", &["red"], "Erste."),
+                description("en", &["sword"], "Second."),
+            ],
+        }))
+        .boxed()
+    }
+}
+
+/// Fake translation service that is always rate limited, counting the requests made to it.
+#[derive(Default)]
+struct RateLimitedService {
+    calls: AtomicUsize,
+}
+
+impl TranslationService for RateLimitedService {
+    fn attempt_translation<'a>(
+        &'a self,
+        _text: &'a str,
+    ) -> BoxFuture<'a, Result<String, TranslationError>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        ready(Err(TranslationError::RateLimited(RateLimit::default()))).boxed()
+    }
+}
+
+fn make_descriptions_cache() -> Arc<DescriptionsCache> {
+    Arc::new(DescriptionsCache::new(
+        NonZeroUsize::new(CAPACITY).unwrap(),
+        TTL,
+    ))
+}
+
+#[tokio::test]
+async fn translated_descriptions() {
+    let cache = make_descriptions_cache();
+
+    let result = super::handle_descriptions_request(
+        "name".to_string(),
+        Some(shakespeare(Arc::new(FakeTranslationService::Succeed))),
+        Arc::new(FakeDescriptionsService),
+        cache.clone(),
+    )
+    .await
+    .unwrap();
+
+    let descriptions = result
+        .descriptions
+        .iter()
+        .map(|d| (d.description.as_str(), d.translated, d.reason.is_some()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        descriptions,
+        vec![
+            ("FIRST.", true, false),
+            ("Erste.", false, true),
+            ("SECOND.", true, false)
+        ]
+    );
+
+    // The complete response is cached.
+    let cached = super::handle_descriptions_request(
+        "name".to_string(),
+        Some(shakespeare(Arc::new(FakeTranslationService::Unavailable))),
+        Arc::new(FakeDescriptionsService),
+        cache,
+    )
+    .await;
+    assert_eq!(cached, Ok(result));
+}
+
+#[tokio::test]
+async fn fallback_descriptions_not_cached() {
+    let cache = make_descriptions_cache();
+
+    let result = super::handle_descriptions_request(
+        "name".to_string(),
+        Some(shakespeare(Arc::new(fallback_chain(
+            FakeTranslationService::Unavailable,
+        )))),
+        Arc::new(FakeDescriptionsService),
+        cache.clone(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        result.descriptions[0].translator,
+        Some("second".to_string())
+    );
+
+    let retried = super::handle_descriptions_request(
+        "name".to_string(),
+        Some(shakespeare(Arc::new(FakeTranslationService::Fail))),
+        Arc::new(FakeDescriptionsService),
+        cache,
+    )
+    .await
+    .unwrap();
+    assert!(!retried.descriptions[0].translated);
+}
+
+#[tokio::test]
+async fn untranslated_descriptions() {
+    let result = super::handle_descriptions_request(
+        "name".to_string(),
+        None::<Translator<FakeTranslationService>>,
+        Arc::new(FakeDescriptionsService),
+        make_descriptions_cache(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        result.descriptions[0],
+        description("en", &["red", "blue"], "First.")
+    );
+    assert!(result
+        .descriptions
+        .iter()
+        .all(|d| !d.translated && d.reason.is_none()));
+}
+
+#[tokio::test]
+async fn descriptions_rate_limited() {
+    let cache = make_descriptions_cache();
+    let translator = Arc::new(RateLimitedService::default());
+
+    let result = super::handle_descriptions_request(
+        "name".to_string(),
+        Some(shakespeare(translator.clone())),
+        Arc::new(FakeDescriptionsService),
+        cache.clone(),
+    )
+    .await
+    .unwrap();
+
+    // No further translations are attempted once the service is rate limited.
+    assert_eq!(translator.calls.load(Ordering::SeqCst), 1);
+    assert!(result
+        .descriptions
+        .iter()
+        .all(|d| !d.translated && d.reason.is_some()));
+
+    // Incomplete responses are not cached.
+    let result = super::handle_descriptions_request(
+        "name".to_string(),
+        Some(shakespeare(Arc::new(FakeTranslationService::Succeed))),
+        Arc::new(FakeDescriptionsService),
+        cache,
+    )
+    .await
+    .unwrap();
+    assert!(result.descriptions[0].translated);
+}
+
+#[tokio::test]
+async fn filter_descriptions() {
+    let filter = super::make_descriptions_filter(
+        Arc::new(FakeDescriptionsService),
+        Arc::new(registry(FakeTranslationService::Succeed)),
+        DescriptionsCache::new(NonZeroUsize::new(CAPACITY).unwrap(), TTL),
+        Duration::from_secs(5),
+    );
+
+    let result = warp::test::request()
+        .path("/pokemon/name/descriptions?translate=true")
+        .filter(&filter)
+        .await
+        .unwrap();
+    assert_eq!(result.name, "name");
+    assert_eq!(result.descriptions[0].description, "FIRST.");
+
+    let result = warp::test::request()
+        .path("/pokemon/name/descriptions")
+        .filter(&filter)
+        .await
+        .unwrap();
+    assert_eq!(result.descriptions[0].description, "First.");
 }
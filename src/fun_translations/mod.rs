@@ -0,0 +1,277 @@
+mod model;
+#[cfg(test)]
+mod tests;
+
+use crate::fun_translations::model::TranslationResponse;
+use crate::retry::{send_with_retry, RetryPolicy};
+use crate::services::translation::{RateLimit, TranslationError, TranslationService};
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+use reqwest::{Client, Error, StatusCode, Url};
+use std::fmt::{Debug, Formatter};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{event, Level};
+
+/// Secret for the paid tier of the translation API. The secret is never included in debug output
+/// or logs.
+#[derive(Clone)]
+pub struct ApiKey(HeaderValue);
+
+impl ApiKey {
+    /// Create a key from the secret, if it is valid as the value of an HTTP header.
+    pub fn new(secret: &str) -> Option<Self> {
+        HeaderValue::from_str(secret).ok().map(|mut value| {
+            value.set_sensitive(true);
+            ApiKey(value)
+        })
+    }
+}
+
+impl Debug for ApiKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ApiKey(<redacted>)")
+    }
+}
+
+/// A translation service for one style of translation (for example "shakespeare" or "yoda")
+/// provided by the API at https://funtranslations.com/api.
+pub struct FunTranslationsService {
+    client: Client,
+    url: Url,
+    style: String,
+    retry: RetryPolicy,
+    api_key: Option<ApiKey>,
+    /// If the rate limit for the API has been exceeded, the time before which no further requests
+    /// should be made. This is shared between the styles as the limit applies to the whole API.
+    blocked_until: Arc<Mutex<Option<Instant>>>,
+}
+
+impl FunTranslationsService {
+    /// # Arguments
+    /// * `client` HTTP client for making requests to the remote API.
+    /// * `url` The URL of the translation endpoint.
+    /// * `style` The style of translation performed by the endpoint.
+    /// * `retry` Policy for retrying failed requests.
+    /// * `api_key` Secret for the paid tier of the API, sent with every request.
+    pub fn new(
+        client: Client,
+        url: Url,
+        style: &str,
+        retry: RetryPolicy,
+        api_key: Option<ApiKey>,
+    ) -> Self {
+        FunTranslationsService {
+            client,
+            url,
+            style: style.to_string(),
+            retry,
+            api_key,
+            blocked_until: Default::default(),
+        }
+    }
+
+    /// Create a service for the same endpoint that sends the secret for the paid tier of the API
+    /// with every request. The paid tier has its own rate limit.
+    ///
+    /// # Arguments
+    /// * `api_key` The secret for the paid tier.
+    pub fn with_api_key(&self, api_key: ApiKey) -> Self {
+        FunTranslationsService {
+            client: self.client.clone(),
+            url: self.url.clone(),
+            style: self.style.clone(),
+            retry: self.retry.clone(),
+            api_key: Some(api_key),
+            blocked_until: Default::default(),
+        }
+    }
+
+    /// Create a service for another style of translation from the same API. The endpoint for the
+    /// style is a sibling of this endpoint (for example `.../translate/yoda.json` for
+    /// `.../translate/shakespeare.json`) and the services share the rate limit of the API.
+    ///
+    /// # Arguments
+    /// * `style` The style of translation.
+    pub fn for_style(&self, style: &str) -> Option<Self> {
+        let url = if style == self.style {
+            self.url.clone()
+        } else {
+            self.url.join(&format!("{}.json", style)).ok()?
+        };
+        Some(FunTranslationsService {
+            client: self.client.clone(),
+            url,
+            style: style.to_string(),
+            retry: self.retry.clone(),
+            api_key: self.api_key.clone(),
+            blocked_until: self.blocked_until.clone(),
+        })
+    }
+
+    /// If the rate limit was exceeded and its window has not yet expired, the time remaining.
+    fn remaining_block(&self, now: Instant) -> Option<Duration> {
+        let mut blocked_until = self.blocked_until.lock().unwrap();
+        match *blocked_until {
+            Some(until) if until > now => Some(until - now),
+            Some(_) => {
+                *blocked_until = None;
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn block_until(&self, until: Instant) {
+        *self.blocked_until.lock().unwrap() = Some(until);
+    }
+}
+
+impl From<reqwest::Error> for TranslationError {
+    fn from(e: Error) -> Self {
+        if e.is_timeout() {
+            TranslationError::Timeout
+        } else {
+            TranslationError::ServiceUnavailable
+        }
+    }
+}
+
+/// Name of the remote service (for logging).
+const SERVICE_NAME: &str = "Fun Translations API";
+
+/// Form key for the translation requests.
+const FORM_KEY: &str = "text";
+
+/// Header for the secret for the paid tier of the API.
+const API_KEY_HEADER: &str = "x-funtranslations-api-secret";
+
+/// The style of the translation at the default endpoint.
+pub const SHAKESPEARE: &str = "shakespeare";
+
+/// How long to stop making requests after exceeding the rate limit, if the API does not say.
+const DEFAULT_RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// The longest time for which requests are stopped after exceeding the rate limit, whatever the API
+/// says.
+const MAX_RATE_LIMIT_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Values of the X-RateLimit-Reset header from this (a Unix timestamp in 2001) are taken to be
+/// timestamps rather than a number of seconds. Every timestamp later than the current time is.
+const MIN_RESET_TIMESTAMP: u64 = 1_000_000_000;
+
+const RATE_LIMIT_LIMIT: &str = "x-ratelimit-limit";
+const RATE_LIMIT_REMAINING: &str = "x-ratelimit-remaining";
+const RATE_LIMIT_RESET: &str = "x-ratelimit-reset";
+
+impl TranslationService for FunTranslationsService {
+    fn attempt_translation<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<String, TranslationError>> {
+        async move {
+            if let Some(remaining) = self.remaining_block(Instant::now()) {
+                event!(Level::DEBUG, message = "Not calling the translation API as the rate limit has been exceeded.", style = %self.style, remaining_secs = remaining.as_secs());
+                return Err(TranslationError::RateLimited(RateLimit {
+                    retry_after: Some(remaining),
+                    ..Default::default()
+                }));
+            }
+
+            let FunTranslationsService {
+                client,
+                url,
+                style,
+                retry,
+                api_key,
+                ..
+            } = self;
+
+            event!(Level::INFO, message = "Making query to translation API.", %url, %style, %text, authenticated = api_key.is_some());
+
+            let form_data = [(FORM_KEY, text)];
+            let response = send_with_retry(retry, SERVICE_NAME, || {
+                let request = client.post(url.clone()).form(&form_data);
+                match api_key {
+                    Some(ApiKey(secret)) => request.header(API_KEY_HEADER, secret.clone()),
+                    None => request,
+                }
+            })
+            .await?;
+
+            let status = response.status();
+
+            event!(Level::DEBUG, message = "Received response from translation service.", %status);
+
+            if status.is_success() {
+                match response.json::<TranslationResponse>().await {
+                    Ok(translated) if translated.contents.translation.eq_ignore_ascii_case(style) => {
+                        Ok(translated.contents.translated)
+                    }
+                    Ok(translated) => {
+                        event!(Level::ERROR, message = "The translation service returned an unexpected translation.",
+                            expected = %style, translation = %translated.contents.translation);
+                        Err(TranslationError::TranslationFailed)
+                    }
+                    Err(error) => {
+                        event!(Level::ERROR, message = "The translations serviced failed to translate the text.", %error, %status);
+                        Err(TranslationError::TranslationFailed)
+                    },
+                }
+            } else if status == StatusCode::TOO_MANY_REQUESTS {
+                let mut rate_limit = rate_limit_from_headers(response.headers(), SystemTime::now());
+                let window = *rate_limit.retry_after.get_or_insert(DEFAULT_RATE_LIMIT_WINDOW);
+                event!(Level::WARN, message = "The translation service rate limit has been exceeded.", window_secs = window.as_secs());
+                let now = Instant::now();
+                self.block_until(now.checked_add(window).unwrap_or(now + MAX_RATE_LIMIT_WINDOW));
+                Err(TranslationError::RateLimited(rate_limit))
+            } else {
+                event!(Level::ERROR, message = "Unanticipated response from translation service.", %status);
+                Err(TranslationError::ServiceUnavailable)
+            }
+        }
+        .boxed()
+    }
+}
+
+/// Extract the details of the rate limit from the headers of a response. The time to wait is taken
+/// from the Retry-After header (if it is a number of seconds) or, failing that, from the
+/// X-RateLimit-Reset header (either the number of seconds until the window resets or the time at
+/// which it resets, as a Unix timestamp). The time to wait is at most `MAX_RATE_LIMIT_WINDOW`.
+///
+/// # Arguments
+/// * `headers` - The headers of the response.
+/// * `now` - The time at which the response was received.
+fn rate_limit_from_headers(headers: &HeaderMap, now: SystemTime) -> RateLimit {
+    let retry_after = header_value::<u64>(headers, RETRY_AFTER.as_str())
+        .or_else(|| {
+            header_value::<u64>(headers, RATE_LIMIT_RESET).map(|reset| seconds_until(reset, now))
+        })
+        .map(|secs| Duration::from_secs(secs).min(MAX_RATE_LIMIT_WINDOW));
+    RateLimit {
+        retry_after,
+        limit: header_value(headers, RATE_LIMIT_LIMIT),
+        remaining: header_value(headers, RATE_LIMIT_REMAINING),
+    }
+}
+
+/// The number of seconds until the rate limit window resets, from the X-RateLimit-Reset header.
+fn seconds_until(reset: u64, now: SystemTime) -> u64 {
+    if reset < MIN_RESET_TIMESTAMP {
+        reset
+    } else {
+        let now = now
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since_epoch| since_epoch.as_secs());
+        reset.saturating_sub(now)
+    }
+}
+
+fn header_value<T: FromStr>(headers: &HeaderMap, name: &str) -> Option<T> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
@@ -0,0 +1,265 @@
+use crate::fun_translations::model::{Translation, TranslationResponse};
+use crate::fun_translations::{ApiKey, FunTranslationsService, SHAKESPEARE};
+use crate::retry::RetryPolicy;
+use crate::services::translation::{RateLimit, TranslationError, TranslationService};
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Client, Url};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use warp::Filter;
+
+const SAMPLE: &str = include_str!("sample.json");
+
+const OUTPUT: &str = "Thee did giveth mr. Tim a hearty meal,  but unfortunately what he did doth englut did maketh him kicketh the bucket.";
+
+#[test]
+fn deserialize_service_response() {
+    let result = serde_json::from_str::<TranslationResponse>(SAMPLE);
+    assert!(result.is_ok());
+
+    let TranslationResponse {
+        contents: Translation {
+            translated,
+            translation,
+        },
+    } = result.unwrap();
+
+    assert_eq!(translation, "shakespeare");
+    assert_eq!(translated, OUTPUT);
+}
+
+#[test]
+fn rate_limit_headers() {
+    let mut headers = HeaderMap::new();
+    headers.insert("Retry-After", HeaderValue::from_static("120"));
+    headers.insert("X-RateLimit-Limit", HeaderValue::from_static("10"));
+    headers.insert("X-RateLimit-Remaining", HeaderValue::from_static("0"));
+    headers.insert("X-RateLimit-Reset", HeaderValue::from_static("3600"));
+
+    assert_eq!(
+        super::rate_limit_from_headers(&headers, SystemTime::now()),
+        RateLimit {
+            retry_after: Some(Duration::from_secs(120)),
+            limit: Some(10),
+            remaining: Some(0),
+        }
+    );
+
+    headers.remove("Retry-After");
+    assert_eq!(
+        super::rate_limit_from_headers(&headers, SystemTime::now()).retry_after,
+        Some(Duration::from_secs(3600))
+    );
+
+    assert_eq!(
+        super::rate_limit_from_headers(&HeaderMap::new(), SystemTime::now()),
+        RateLimit::default()
+    );
+}
+
+#[test]
+fn rate_limit_window_limited() {
+    let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Retry-After",
+        HeaderValue::from_static("18446744073709551615"),
+    );
+    assert_eq!(
+        super::rate_limit_from_headers(&headers, now).retry_after,
+        Some(super::MAX_RATE_LIMIT_WINDOW)
+    );
+
+    // A reset time given as a timestamp.
+    let mut headers = HeaderMap::new();
+    headers.insert("X-RateLimit-Reset", HeaderValue::from_static("1700000090"));
+    assert_eq!(
+        super::rate_limit_from_headers(&headers, now).retry_after,
+        Some(Duration::from_secs(90))
+    );
+    headers.insert("X-RateLimit-Reset", HeaderValue::from_static("1699999990"));
+    assert_eq!(
+        super::rate_limit_from_headers(&headers, now).retry_after,
+        Some(Duration::ZERO)
+    );
+}
+
+#[tokio::test]
+async fn huge_retry_after_does_not_panic() {
+    let route = warp::post().map(|| {
+        warp::reply::with_header(
+            warp::reply::with_status("", warp::http::StatusCode::TOO_MANY_REQUESTS),
+            "Retry-After",
+            "18446744073709551615",
+        )
+    });
+    let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    let url = Url::parse(&format!("http://{}/translate/shakespeare.json", addr)).unwrap();
+    let service = FunTranslationsService::new(
+        Client::new(),
+        url,
+        SHAKESPEARE,
+        RetryPolicy::default(),
+        None,
+    );
+
+    match service.attempt_translation("text").await {
+        Err(TranslationError::RateLimited(RateLimit { retry_after, .. })) => {
+            assert_eq!(retry_after, Some(super::MAX_RATE_LIMIT_WINDOW))
+        }
+        ow => panic!("Unexpected result: {:?}", ow),
+    }
+}
+
+#[tokio::test]
+async fn no_requests_while_rate_limited() {
+    // Nothing is listening on the discard port so any request would fail as unavailable.
+    let url = Url::parse("http://127.0.0.1:9/translate/shakespeare.json").unwrap();
+    let service = FunTranslationsService::new(
+        Client::new(),
+        url,
+        SHAKESPEARE,
+        RetryPolicy::default(),
+        None,
+    );
+    service.block_until(Instant::now() + Duration::from_secs(60));
+
+    let result = service.attempt_translation("text").await;
+
+    match result {
+        Err(TranslationError::RateLimited(RateLimit {
+            retry_after: Some(retry_after),
+            ..
+        })) => {
+            assert!(retry_after <= Duration::from_secs(60));
+        }
+        ow => panic!("Unexpected result: {:?}", ow),
+    }
+}
+
+#[test]
+fn api_key_redacted() {
+    let key = ApiKey::new("top-secret").unwrap();
+    assert!(!format!("{:?}", key).contains("top-secret"));
+}
+
+#[tokio::test]
+async fn api_key_sent() {
+    let route = warp::post()
+        .and(warp::header::optional::<String>(
+            "X-Funtranslations-Api-Secret",
+        ))
+        .map(|secret: Option<String>| {
+            let translated = match secret.as_deref() {
+                Some("top-secret") => "Authenticated",
+                _ => "Anonymous",
+            };
+            warp::reply::json(&serde_json::json!({
+                "contents": { "translated": translated, "translation": "shakespeare" }
+            }))
+        });
+    let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    let url = Url::parse(&format!("http://{}/translate/shakespeare.json", addr)).unwrap();
+
+    let anonymous = FunTranslationsService::new(
+        Client::new(),
+        url.clone(),
+        SHAKESPEARE,
+        RetryPolicy::default(),
+        None,
+    );
+    assert_eq!(
+        anonymous.attempt_translation("text").await,
+        Ok("Anonymous".to_string())
+    );
+
+    let key = ApiKey::new("top-secret");
+    let authenticated =
+        FunTranslationsService::new(Client::new(), url, SHAKESPEARE, RetryPolicy::default(), key);
+    assert_eq!(
+        authenticated.attempt_translation("text").await,
+        Ok("Authenticated".to_string())
+    );
+}
+
+#[tokio::test]
+async fn rate_limit_shared_between_styles() {
+    let url = Url::parse("http://127.0.0.1:9/translate/shakespeare.json").unwrap();
+    let service = FunTranslationsService::new(
+        Client::new(),
+        url,
+        SHAKESPEARE,
+        RetryPolicy::default(),
+        None,
+    );
+    let yoda = service.for_style("yoda").unwrap();
+    assert_eq!(yoda.url.as_str(), "http://127.0.0.1:9/translate/yoda.json");
+
+    service.block_until(Instant::now() + Duration::from_secs(60));
+    assert!(matches!(
+        yoda.attempt_translation("text").await,
+        Err(TranslationError::RateLimited(_))
+    ));
+}
+
+#[tokio::test]
+async fn translation_style_validated() {
+    let route = warp::path!("translate" / String).map(|endpoint: String| {
+        let style = endpoint.trim_end_matches(".json").to_string();
+        // The pirate endpoint responds with the wrong translation.
+        let translation = if style == "pirate" {
+            "shakespeare".to_string()
+        } else {
+            style.clone()
+        };
+        warp::reply::json(&serde_json::json!({
+            "contents": { "translated": format!("{} text", style), "translation": translation }
+        }))
+    });
+    let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    let url = Url::parse(&format!("http://{}/translate/shakespeare.json", addr)).unwrap();
+    let service = FunTranslationsService::new(
+        Client::new(),
+        url,
+        SHAKESPEARE,
+        RetryPolicy::default(),
+        None,
+    );
+
+    assert_eq!(
+        service.attempt_translation("text").await,
+        Ok("shakespeare text".to_string())
+    );
+    let yoda = service.for_style("yoda").unwrap();
+    assert_eq!(
+        yoda.attempt_translation("text").await,
+        Ok("yoda text".to_string())
+    );
+    let pirate = service.for_style("pirate").unwrap();
+    assert_eq!(
+        pirate.attempt_translation("text").await,
+        Err(TranslationError::TranslationFailed)
+    );
+}
+
+#[cfg(feature = "api_tests")]
+const SERVICE_URL: &str = "https://api.funtranslations.com/translate/shakespeare.json";
+
+#[cfg(feature = "api_tests")]
+const INPUT: &str = "You gave Mr. Tim a hearty meal, but unfortunately what he ate made him die.";
+
+#[cfg(feature = "api_tests")]
+#[tokio::test]
+async fn call_service() {
+    let url = Url::parse(SERVICE_URL).unwrap();
+    let client = Client::new();
+
+    let service =
+        FunTranslationsService::new(client, url, SHAKESPEARE, RetryPolicy::default(), None);
+
+    let result = service.attempt_translation(INPUT).await;
+
+    assert_eq!(result, Ok(OUTPUT.to_string()));
+}
@@ -1,76 +1,324 @@
+mod cache;
+mod chain;
+mod chunking;
+mod circuit_breaker;
+mod coalescing;
+mod config;
+mod dictionary;
 mod endpoints;
+mod fun_translations;
 pub mod model;
+mod names;
 mod poke_api;
+mod retry;
 pub mod services;
-mod shakespeare_api;
+mod species_index;
+mod tls;
+mod translation_store;
 
-use crate::poke_api::PokeApiService;
-use crate::shakespeare_api::ShakespeareService;
+use crate::cache::TtlCache;
+use crate::chain::TranslatorChain;
+use crate::chunking::{ChunkingConfig, ChunkingTranslationService};
+use crate::circuit_breaker::{BreakerConfig, Circuit, CircuitBreaker};
+use crate::coalescing::{CoalescingPokemonService, CoalescingTranslationService};
+use crate::config::{Config, Params, TranslatorStep};
+use crate::dictionary::DictionaryTranslationService;
+use crate::endpoints::{DescriptionsCache, EndpointSettings, ResponseCache, ResponseCaches};
+use crate::fun_translations::FunTranslationsService;
+use crate::poke_api::{FlavorTextCleaner, PokeApiService};
+use crate::services::pokemon::DescriptionPreferences;
+use crate::services::translation::{
+    DynTranslationService, TranslationError, TranslationService, TranslatorRegistry,
+};
+use crate::species_index::{SpeciesIndex, SuggestingPokemonService};
+use crate::tls::{ReloadableCertResolver, TlsConfig};
+use crate::translation_store::PersistentTranslationService;
 use clap::Clap;
-use reqwest::Url;
-use std::net::{IpAddr, SocketAddr};
+use sled::Db;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use tracing::{event, span, Instrument, Level};
+use tracing_subscriber::EnvFilter;
 
-#[derive(Clap)]
-#[clap(name = "pokemon-translator", version = "0.1")]
-struct Params {
-    /// Address to bind to
-    #[clap(short, long, default_value = "127.0.0.1")]
-    bind: String,
-    /// Port to bind to
-    #[clap(short, long, default_value = "8080")]
-    port: u16,
-    /// Base URL of the Pokemon API (i.e. https://pokeapi.co/api/v2/pokemon-species)
-    #[clap(short = 'a', long)]
-    pokemon: String,
-    /// URL of the Shakespeare translation service (i.e. https://api.funtranslations.com/translate/shakespeare.json)
-    #[clap(short, long)]
-    shakespeare: String,
+#[tokio::main]
+async fn main() {
+    let params = Params::parse().load();
+    init_logging(params.as_ref().ok());
+
+    match params.and_then(Params::validate) {
+        Ok(config) => {
+            run_service(config)
+                .instrument(span!(Level::INFO, "Pokemon API server."))
+                .await;
+        }
+        Err(msg) => {
+            event!(Level::ERROR, message = "Configuration parameters were invalid.", error = %msg);
+            panic!("{}", msg);
+        }
+    }
 }
 
-impl Params {
-    fn validate(self) -> Result<(SocketAddr, Url, Url), String> {
-        let Params {
-            bind,
-            port,
-            pokemon,
-            shakespeare,
-        } = self;
+/// Initialize logging. The RUST_LOG environment variable takes precedence over the configured
+/// filter.
+fn init_logging(params: Option<&Params>) {
+    let filter = EnvFilter::try_from_default_env()
+        .ok()
+        .or_else(|| params.and_then(|p| EnvFilter::try_new(p.log_filter()).ok()))
+        .unwrap_or_default();
+    tracing_subscriber::fmt().with_env_filter(filter).init();
+}
 
-        let addr: IpAddr = bind
-            .parse()
-            .map_err(|_| format!("{} is not a valid IP.", bind))?;
+/// Assemble the services from the configuration and run the server.
+async fn run_service(config: Config) {
+    let Config {
+        sock_addr,
+        poke_api_url,
+        shakespeare_url,
+        cache_capacity,
+        cache_ttl,
+        translation_store,
+        fallback,
+        retry_policy,
+        breaker_config,
+        pokemon_timeouts,
+        shakespeare_timeouts,
+        deadline,
+        batch_concurrency,
+        tls,
+        shakespeare_api_key,
+        species_refresh,
+        auto_resolve,
+        versions,
+        languages,
+        normalise_casing,
+        styles,
+        translation_chain,
+        chunking,
+    } = config;
 
-        let sock_addr = SocketAddr::new(addr, port);
+    let (pokemon_client, shakespeare_client) = match (
+        pokemon_timeouts.make_client(),
+        shakespeare_timeouts.make_client(),
+    ) {
+        (Ok(pokemon_client), Ok(shakespeare_client)) => (pokemon_client, shakespeare_client),
+        (Err(error), _) | (_, Err(error)) => {
+            event!(Level::ERROR, message = "Failed to create HTTP client.", %error);
+            panic!("{}", error);
+        }
+    };
 
-        let poke_api_url =
-            Url::parse(pokemon.as_str()).map_err(|_| format!("{} is not a valid URL.", pokemon))?;
+    let cleaner = FlavorTextCleaner { normalise_casing };
+    let species_index = Arc::new(SpeciesIndex::default());
+    species_index::spawn_refresh(
+        species_index.clone(),
+        PokeApiService::new(
+            pokemon_client.clone(),
+            poke_api_url.clone(),
+            retry_policy.clone(),
+            cleaner,
+        ),
+        species_refresh,
+    );
 
-        let shakespeare_url = Url::parse(shakespeare.as_str())
-            .map_err(|_| format!("{} is not a valid URL.", shakespeare))?;
+    let poke_api_service = CircuitBreaker::new(
+        PokeApiService::new(pokemon_client, poke_api_url, retry_policy.clone(), cleaner),
+        "pokeapi",
+        breaker_config,
+    );
+    let mut circuits = vec![poke_api_service.circuit()];
 
-        Ok((sock_addr, poke_api_url, shakespeare_url))
+    let pokemon_service = CoalescingPokemonService::new(SuggestingPokemonService::new(
+        poke_api_service,
+        species_index,
+        auto_resolve,
+    ));
+    let free = FunTranslationsService::new(
+        shakespeare_client,
+        shakespeare_url,
+        fun_translations::SHAKESPEARE,
+        retry_policy,
+        None,
+    );
+    let factory = TranslatorFactory {
+        paid: shakespeare_api_key.map(|key| free.with_api_key(key)),
+        free,
+        breaker_config,
+        store: translation_store.map(open_translation_store),
+        chunking,
+        cache_capacity,
+        cache_ttl,
+    };
+    let mut translators: Option<TranslatorRegistry<TranslatorChain>> = None;
+    for style in styles {
+        let translator = factory.make_chain(style.as_str(), &translation_chain, &mut circuits);
+        match translators.as_mut() {
+            Some(registry) => registry.register(style.as_str(), translator),
+            // The first style is the default.
+            None => translators = Some(TranslatorRegistry::new(style.as_str(), translator)),
+        }
     }
+    let translators = translators.expect("At least one style of translation is required.");
+    let caches = ResponseCaches {
+        pokemon: ResponseCache::new(cache_capacity, cache_ttl),
+        descriptions: DescriptionsCache::new(cache_capacity, cache_ttl),
+    };
+    let tls = tls.map(load_certificate);
+    let settings = EndpointSettings {
+        fallback,
+        deadline,
+        batch_concurrency: batch_concurrency.get(),
+        preferences: DescriptionPreferences {
+            versions,
+            languages,
+        },
+    };
+    endpoints::run_server(
+        sock_addr,
+        tls,
+        pokemon_service,
+        translators,
+        caches,
+        circuits,
+        settings,
+    )
+    .await;
 }
 
-#[tokio::main]
-async fn main() {
-    tracing_subscriber::fmt::init();
-    let params: Params = Params::parse();
+/// Makes the translators for each style of translation.
+struct TranslatorFactory {
+    /// The translation service, using the API key (if there is one).
+    paid: Option<FunTranslationsService>,
+    /// The translation service, without the API key.
+    free: FunTranslationsService,
+    breaker_config: BreakerConfig,
+    store: Option<Db>,
+    chunking: ChunkingConfig,
+    /// The capacity and time to live of the caches for the translations of chunks of text.
+    cache_capacity: NonZeroUsize,
+    cache_ttl: Duration,
+}
 
-    match params.validate() {
-        Ok((sock_addr, poke_api_url, shakespeare_url)) => {
-            let client = reqwest::Client::new();
+impl TranslatorFactory {
+    /// Assemble the chain of translators for a style of translation. The dictionary only provides
+    /// Shakespearian translations so it is left out of the chains for the other styles.
+    ///
+    /// # Arguments
+    /// * `style` - The style of translation.
+    /// * `steps` - The configured chain of translators.
+    /// * `circuits` - Collects the circuit breakers of the translators.
+    fn make_chain(
+        &self,
+        style: &str,
+        steps: &[TranslatorStep],
+        circuits: &mut Vec<Arc<Circuit>>,
+    ) -> TranslatorChain {
+        let mut steps = steps
+            .iter()
+            .copied()
+            .filter(|step| {
+                style == fun_translations::SHAKESPEARE || *step != TranslatorStep::Dictionary
+            })
+            .collect::<Vec<_>>();
+        if steps.is_empty() {
+            steps.push(if self.paid.is_some() {
+                TranslatorStep::FunTranslationsPaid
+            } else {
+                TranslatorStep::FunTranslations
+            });
+        }
+        let remote_steps = steps
+            .iter()
+            .filter(|step| **step != TranslatorStep::Dictionary)
+            .count();
 
-            let pokemon_service = PokeApiService::new(client.clone(), poke_api_url);
-            let shakespeare_service = ShakespeareService::new(client, shakespeare_url);
-            endpoints::run_server(sock_addr, pokemon_service, shakespeare_service)
-                .instrument(span!(Level::INFO, "Pokemon API server."))
-                .await;
+        let mut chain = TranslatorChain::default();
+        for step in steps {
+            let base = match step {
+                TranslatorStep::Dictionary => {
+                    chain = chain.then(
+                        step.name(),
+                        DictionaryTranslationService::shakespeare(),
+                        TranslationError::is_transient,
+                    );
+                    continue;
+                }
+                TranslatorStep::FunTranslationsPaid => self
+                    .paid
+                    .as_ref()
+                    .expect("The paid translation service requires an API key."),
+                TranslatorStep::FunTranslations => &self.free,
+            };
+            let service = match base.for_style(style) {
+                Some(service) => service,
+                None => {
+                    event!(Level::ERROR, message = "Failed to make the URL for a style of translation.", %style);
+                    panic!("Invalid style of translation: {}", style);
+                }
+            };
+            // Each translation service has its own circuit, named after the style.
+            let circuit_name = if remote_steps == 1 {
+                style.to_string()
+            } else {
+                format!("{}-{}", style, step.name())
+            };
+            let service = CircuitBreaker::new(service, circuit_name.as_str(), self.breaker_config);
+            circuits.push(service.circuit());
+            let service = CoalescingTranslationService::new(with_translation_store(
+                service,
+                style,
+                self.store.clone(),
+            ));
+            chain = chain.then(
+                step.name(),
+                ChunkingTranslationService::new(
+                    service,
+                    self.chunking,
+                    TtlCache::new(self.cache_capacity, self.cache_ttl),
+                ),
+                TranslationError::is_transient,
+            );
         }
-        Err(msg) => {
-            event!(Level::ERROR, message = "Configuration parameters were invalid.", error = %msg);
-            panic!(msg);
+        chain
+    }
+}
+
+/// Open the persistent store for translations. The store is shared by all styles of translation.
+fn open_translation_store(path: PathBuf) -> Db {
+    match sled::open(&path) {
+        Ok(db) => db,
+        Err(error) => {
+            event!(Level::ERROR, message = "Failed to open the translation store.", path = %path.display(), %error);
+            panic!("{}", error);
+        }
+    }
+}
+
+/// Wrap the translation service with the persistent store, if one was configured.
+fn with_translation_store<T>(service: T, style: &str, db: Option<Db>) -> DynTranslationService
+where
+    T: TranslationService + Send + Sync + 'static,
+{
+    if let Some(db) = db {
+        Box::new(PersistentTranslationService::new(service, style, db))
+    } else {
+        Box::new(service)
+    }
+}
+
+/// Load the TLS certificate and arrange for it to be reloaded on SIGHUP.
+fn load_certificate(config: TlsConfig) -> Arc<ReloadableCertResolver> {
+    let resolver = match ReloadableCertResolver::load(config) {
+        Ok(resolver) => Arc::new(resolver),
+        Err(error) => {
+            event!(Level::ERROR, message = "Failed to load the TLS certificate.", %error);
+            panic!("{}", error);
         }
+    };
+    #[cfg(unix)]
+    if let Err(error) = tls::reload_on_hangup(resolver.clone()) {
+        event!(Level::ERROR, message = "Failed to listen for SIGHUP. The TLS certificate will not be reloaded.", %error);
     }
+    resolver
 }
@@ -1,20 +1,113 @@
+use crate::circuit_breaker::CircuitStatus;
 use serde::Serialize;
 
 /// Model for the return type for our service endpoint.
 #[derive(Serialize, Debug, Clone, PartialEq, Eq)]
 pub struct PokemonData {
+    /// The name of the species.
     pub name: String,
+    /// If a variety or form of the species was requested, its name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variety: Option<String>,
     pub description: String,
+    /// The language of the (untranslated) description.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Whether the description has been translated.
+    pub translated: bool,
+    /// If the description has been translated, the translator that produced it (where known).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translator: Option<String>,
+    /// If the description could not be translated, the reason why.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
 }
 
-#[derive(Serialize, Debug)]
+impl PokemonData {
+    /// Create an entry with an untranslated description.
+    pub fn new(name: String, description: String) -> Self {
+        PokemonData {
+            name,
+            variety: None,
+            description,
+            language: None,
+            translated: false,
+            translator: None,
+            reason: None,
+        }
+    }
+}
+
+/// Model for the descriptions endpoint: every distinct description of a species.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PokemonDescriptions {
+    /// The name of the species.
+    pub name: String,
+    /// If a variety or form of the species was requested, its name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variety: Option<String>,
+    /// The descriptions, in the order of the versions of the game in which they first appeared.
+    pub descriptions: Vec<Description>,
+}
+
+/// A description of a species that appeared in one or more versions of the game.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Description {
+    /// The language of the (untranslated) description.
+    pub language: String,
+    /// The versions of the game in which the description appeared.
+    pub versions: Vec<String>,
+    pub description: String,
+    /// Whether the description has been translated.
+    pub translated: bool,
+    /// If the description has been translated, the translator that produced it (where known).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translator: Option<String>,
+    /// If the description could not be translated, the reason why.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Model for the status endpoint.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ServiceStatus {
+    pub circuits: Vec<CircuitStatus>,
+}
+
+/// Model for the batch endpoint: the result for each of the requested names, in the order in which
+/// they were requested.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PokemonBatch {
+    pub results: Vec<BatchEntry>,
+}
+
+/// The result of looking up one of the names in a batch: either the Pokemon or an error.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct BatchEntry {
+    /// The name as it was requested.
+    pub name: String,
+    /// The HTTP status that a request for this name alone would have received.
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pokemon: Option<PokemonData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorMessage>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
 /// Model for error responses.
 pub struct ErrorMessage {
     pub message: String,
+    /// Suggested alternatives for a request that could not be satisfied.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
 }
 
 impl ErrorMessage {
     pub fn new(message: String) -> Self {
-        ErrorMessage { message }
+        ErrorMessage {
+            message,
+            suggestions: vec![],
+        }
     }
 }
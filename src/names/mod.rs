@@ -0,0 +1,52 @@
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+#[cfg(test)]
+mod tests;
+
+/// Convert the display name of a Pokemon species (for example "Mr. Mime", "Farfetch'd" or
+/// "Nidoran♀") into the form used by the PokeAPI ("mr-mime", "farfetchd" and "nidoran-f").
+///
+/// The name is lowercased and stripped of accents. The gender symbols are replaced with the
+/// suffixes used by the PokeAPI, punctuation is removed and any remaining spaces, underscores or
+/// hyphens separate the words of the name with a single hyphen. Names that are already in the form
+/// used by the PokeAPI are unchanged.
+pub fn to_slug(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    let mut separator = false;
+    for c in name.nfd().filter(|c| !is_combining_mark(*c)) {
+        let suffix = match c {
+            '♀' => Some('f'),
+            '♂' => Some('m'),
+            _ => None,
+        };
+        if let Some(suffix) = suffix {
+            push_separator(&mut slug);
+            slug.push(suffix);
+            separator = false;
+        } else if c.is_whitespace() || c == '-' || c == '_' {
+            separator = true;
+        } else if is_ignored_punctuation(c) {
+            continue;
+        } else {
+            if separator {
+                push_separator(&mut slug);
+                separator = false;
+            }
+            slug.extend(c.to_lowercase());
+        }
+    }
+    slug
+}
+
+/// Separators are only required between words.
+fn push_separator(slug: &mut String) {
+    if !slug.is_empty() && !slug.ends_with('-') {
+        slug.push('-');
+    }
+}
+
+/// Punctuation that occurs in display names but is dropped by the PokeAPI.
+fn is_ignored_punctuation(c: char) -> bool {
+    matches!(c, '.' | '\'' | '’' | ':')
+}
@@ -0,0 +1,35 @@
+use crate::names::to_slug;
+
+#[test]
+fn slugs_unchanged() {
+    assert_eq!(to_slug("pikachu"), "pikachu");
+    assert_eq!(to_slug("mr-mime"), "mr-mime");
+    assert_eq!(to_slug("nidoran-f"), "nidoran-f");
+    assert_eq!(to_slug("porygon2"), "porygon2");
+}
+
+#[test]
+fn display_names() {
+    assert_eq!(to_slug("Pikachu"), "pikachu");
+    assert_eq!(to_slug("Mr. Mime"), "mr-mime");
+    assert_eq!(to_slug("Mime Jr."), "mime-jr");
+    assert_eq!(to_slug("Farfetch'd"), "farfetchd");
+    assert_eq!(to_slug("Sirfetch’d"), "sirfetchd");
+    assert_eq!(to_slug("Type: Null"), "type-null");
+    assert_eq!(to_slug("Tapu  Koko"), "tapu-koko");
+    assert_eq!(to_slug("Ho-Oh"), "ho-oh");
+    assert_eq!(to_slug(" Porygon-Z "), "porygon-z");
+}
+
+#[test]
+fn gender_symbols() {
+    assert_eq!(to_slug("Nidoran♀"), "nidoran-f");
+    assert_eq!(to_slug("Nidoran♂"), "nidoran-m");
+    assert_eq!(to_slug("Nidoran ♀"), "nidoran-f");
+}
+
+#[test]
+fn accents() {
+    assert_eq!(to_slug("Flabébé"), "flabebe");
+    assert_eq!(to_slug("POKéMON"), "pokemon");
+}
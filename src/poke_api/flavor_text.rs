@@ -0,0 +1,46 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    /// A soft hyphen marks where the game broke a word across lines. Any following line break is
+    /// removed along with it.
+    static ref SOFT_HYPHEN: Regex = Regex::new(r"\u{AD}\s*").unwrap();
+    /// A hyphen at the end of a line that follows a letter.
+    static ref LINE_END_HYPHEN: Regex = Regex::new(r"(\p{L})-[\r\n\x0C]\s*").unwrap();
+    static ref WS: Regex = Regex::new(r"\s+").unwrap();
+    static ref POKE_WORDS: Regex = Regex::new(r"POKé(MON|DEX| BALL)?").unwrap();
+}
+
+/// Cleans the flavor text from the PokeAPI. The text keeps the line breaks and form feeds of the
+/// original game text, which are not wanted in the descriptions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlavorTextCleaner {
+    /// Whether to replace the capitalization of older games (for example "POKéMON") with the
+    /// modern spelling ("Pokémon").
+    pub normalise_casing: bool,
+}
+
+impl FlavorTextCleaner {
+    pub fn clean(&self, text: &str) -> String {
+        let text = SOFT_HYPHEN.replace_all(text, "");
+        // In the PokeAPI text, words that were split across lines are marked with soft hyphens so a
+        // hyphen at the end of a line is part of a compound word (for example "bolt-shaped").
+        let text = LINE_END_HYPHEN.replace_all(text.as_ref(), "$1-");
+        let text = WS.replace_all(text.as_ref(), " ");
+        let text = text.trim();
+        if self.normalise_casing {
+            POKE_WORDS
+                .replace_all(text, |captures: &regex::Captures| {
+                    match captures.get(1).map(|suffix| suffix.as_str()) {
+                        Some("MON") => "Pokémon",
+                        Some("DEX") => "Pokédex",
+                        Some(" BALL") => "Poké Ball",
+                        _ => "Poké",
+                    }
+                })
+                .into_owned()
+        } else {
+            text.to_string()
+        }
+    }
+}
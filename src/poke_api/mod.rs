@@ -1,35 +1,59 @@
-use crate::model::PokemonData;
-use crate::poke_api::model::{FlavorEntry, PokemonSpecies};
-use crate::services::pokemon::{PokemonService, PokemonServiceError};
+use crate::model::{Description, PokemonData, PokemonDescriptions};
+use crate::names::to_slug;
+pub use crate::poke_api::flavor_text::FlavorTextCleaner;
+use crate::poke_api::model::{
+    FlavorEntry, NamedResource, Pokemon, PokemonForm, PokemonSpecies, SpeciesList, VersionGroup,
+};
+use crate::retry::{send_with_retry, RetryPolicy};
+use crate::services::pokemon::{
+    DescriptionPreferences, DescriptionsService, PokemonService, PokemonServiceError,
+    SpeciesListService,
+};
 use futures::future::BoxFuture;
 use futures::FutureExt;
 use lazy_static::lazy_static;
 use regex::Regex;
 use reqwest::{Client, Error, StatusCode, Url};
+use serde::de::DeserializeOwned;
 use tracing::{event, Level};
 
+mod flavor_text;
 mod model;
 #[cfg(test)]
 mod tests;
 
 lazy_static! {
-    static ref WS: Regex = Regex::new(r"\s+").unwrap();
     static ref NAME: Regex = Regex::new(r"^[A-Za-z][A-Za-z0-9\-]*$").unwrap();
+    static ref DEX_NUMBER: Regex = Regex::new(r"^#?([0-9]+)$").unwrap();
 }
 
 /// Implementation of the Pokemon service that queries the PokeAPI at https://pokeapi.co.
 pub struct PokeApiService {
     client: Client,
     base_url: Url,
+    retry: RetryPolicy,
+    cleaner: FlavorTextCleaner,
 }
 
 impl PokeApiService {
     /// # Arguments
     /// * `client` - HTTP client to make remote requests.
     /// * `base-url` - Base url to the service. The name of the Pokemon will be appended as the
-    /// final path segment.
-    pub fn new(client: Client, base_url: Url) -> Self {
-        PokeApiService { client, base_url }
+    ///   final path segment.
+    /// * `retry` - Policy for retrying failed requests.
+    /// * `cleaner` - Cleans the descriptions from the service.
+    pub fn new(
+        client: Client,
+        base_url: Url,
+        retry: RetryPolicy,
+        cleaner: FlavorTextCleaner,
+    ) -> Self {
+        PokeApiService {
+            client,
+            base_url,
+            retry,
+            cleaner,
+        }
     }
 }
 
@@ -43,11 +67,156 @@ impl PokeApiService {
         }
         Ok(url)
     }
+
+    /// The URL of a resource from another endpoint of the PokeAPI (a sibling of the species
+    /// endpoint).
+    fn try_format_sibling_url(
+        &self,
+        endpoint: &str,
+        name: &str,
+    ) -> Result<Url, PokemonServiceError> {
+        let mut url = self.base_url.clone();
+        if let Ok(mut path) = url.path_segments_mut() {
+            path.pop_if_empty().pop().push(endpoint).push(name);
+        } else {
+            return Err(PokemonServiceError::ServiceUnavailable);
+        }
+        Ok(url)
+    }
+
+    /// Fetch a resource from the PokeAPI, if it exists.
+    async fn fetch<T: DeserializeOwned>(&self, url: Url) -> Result<Option<T>, PokemonServiceError> {
+        let PokeApiService { client, retry, .. } = self;
+        event!(Level::DEBUG, message = "Making Pokemon service request to:", %url);
+        let response = send_with_retry(retry, SERVICE_NAME, || client.get(url.clone())).await?;
+        let status = response.status();
+
+        event!(Level::DEBUG, message = "Received response from Pokemon service.", %status);
+
+        if status.is_success() {
+            Ok(Some(response.json::<T>().await?))
+        } else if status == StatusCode::NOT_FOUND {
+            Ok(None)
+        } else {
+            event!(Level::ERROR, message = "Unanticipated response from Pokemon service.", %status);
+            Err(PokemonServiceError::ServiceUnavailable)
+        }
+    }
+
+    /// Resolve the name of a variety (for example "vulpix-alola") or a form (for example
+    /// "unown-b") of a species.
+    async fn find_variety(&self, name: &str) -> Result<Option<Variety>, PokemonServiceError> {
+        let pokemon_url = self.try_format_sibling_url(POKEMON_ENDPOINT, name)?;
+        if let Some(Pokemon {
+            name,
+            species,
+            forms,
+        }) = self.fetch::<Pokemon>(pokemon_url).await?
+        {
+            // The first form is the default form of the variety.
+            let form = if let Some(NamedResource { name: form_name }) = forms.first() {
+                let form_url = self.try_format_sibling_url(FORM_ENDPOINT, form_name)?;
+                self.fetch::<PokemonForm>(form_url).await?
+            } else {
+                None
+            };
+            return Ok(Some(Variety {
+                name,
+                species: species.name,
+                form,
+            }));
+        }
+
+        let form_url = self.try_format_sibling_url(FORM_ENDPOINT, name)?;
+        if let Some(form) = self.fetch::<PokemonForm>(form_url).await? {
+            let pokemon_url = self.try_format_sibling_url(POKEMON_ENDPOINT, &form.pokemon.name)?;
+            let pokemon = self.fetch::<Pokemon>(pokemon_url).await?;
+            Ok(pokemon.map(|Pokemon { species, .. }| Variety {
+                name: form.name.clone(),
+                species: species.name,
+                form: Some(form),
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Find the species with a name. If there is no such species, the name may instead be that of a
+    /// variety or form of a species.
+    async fn find_species(
+        &self,
+        name: &str,
+    ) -> Result<(PokemonSpecies, Option<Variety>), PokemonServiceError> {
+        // Check that the name is reasonable.
+        let segment = if let Some(segment) = species_path_segment(name) {
+            segment
+        } else {
+            event!(Level::INFO, message = "Rejected ill-formed Pokemon name.", %name);
+            return Err(PokemonServiceError::no_such_pokemon(name));
+        };
+
+        let url = self.try_format_url(segment.as_str())?;
+        if let Some(species) = self.fetch::<PokemonSpecies>(url).await? {
+            return Ok((species, None));
+        }
+
+        // The name is not that of a species but it could be a variety or form of one.
+        let variety = if let Some(variety) = self.find_variety(segment.as_str()).await? {
+            variety
+        } else {
+            return Err(PokemonServiceError::no_such_pokemon(name));
+        };
+        let Variety {
+            name: variety_name,
+            species,
+            ..
+        } = &variety;
+        event!(Level::DEBUG, message = "Resolved variety of species.", variety = %variety_name, %species);
+
+        let url = self.try_format_url(species.as_str())?;
+        if let Some(species) = self.fetch::<PokemonSpecies>(url).await? {
+            Ok((species, Some(variety)))
+        } else {
+            event!(Level::ERROR, message = "The species of a variety does not exist.", variety = %variety_name, %species);
+            Err(PokemonServiceError::ServiceUnavailable)
+        }
+    }
+
+    /// The versions of the game in which a form was introduced.
+    async fn form_versions(&self, form: &PokemonForm) -> Result<Vec<String>, PokemonServiceError> {
+        let url = self.try_format_sibling_url(VERSION_GROUP_ENDPOINT, &form.version_group.name)?;
+        Ok(self
+            .fetch::<VersionGroup>(url)
+            .await?
+            .map(|VersionGroup { versions }| {
+                versions
+                    .into_iter()
+                    .map(|NamedResource { name }| name)
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
 }
 
+/// A variety or form of a species.
+struct Variety {
+    name: String,
+    species: String,
+    form: Option<PokemonForm>,
+}
+
+/// Endpoints of the PokeAPI for varieties and forms of species, and for game versions.
+const POKEMON_ENDPOINT: &str = "pokemon";
+const FORM_ENDPOINT: &str = "pokemon-form";
+const VERSION_GROUP_ENDPOINT: &str = "version-group";
+
 impl From<reqwest::Error> for PokemonServiceError {
-    fn from(_: Error) -> Self {
-        PokemonServiceError::ServiceUnavailable
+    fn from(e: Error) -> Self {
+        if e.is_timeout() {
+            PokemonServiceError::Timeout
+        } else {
+            PokemonServiceError::ServiceUnavailable
+        }
     }
 }
 
@@ -55,35 +224,83 @@ impl PokemonService for PokeApiService {
     fn get_pokemon<'a>(
         &'a self,
         name: &'a str,
+        preferences: &'a DescriptionPreferences,
     ) -> BoxFuture<'a, Result<PokemonData, PokemonServiceError>> {
         async move {
-            // Check that the name is reasonable.
-            if !NAME.is_match(name) {
-                event!(Level::INFO, message = "Rejected ill-formed Pokemon name.", %name);
-                return Err(PokemonServiceError::NoSuchPokemon(name.to_string()));
-            }
-
-            let PokeApiService { client, .. } = self;
-            let url = self.try_format_url(name)?;
-            event!(Level::DEBUG, message = "Making Pokemon species request to:", %url);
-            let response = client.get(url).send().await?;
-            let status = response.status();
+            let (species, variety) = self.find_species(name).await?;
 
-            event!(Level::DEBUG, message = "Received response from Pokemon service.", %status);
+            // Explicitly preferred versions take precedence over those in which the form was
+            // introduced.
+            let mut versions = preferences.versions.clone();
+            let variety = if let Some(Variety { name, form, .. }) = variety {
+                if let Some(form) = &form {
+                    versions.extend(self.form_versions(form).await?);
+                }
+                Some(name)
+            } else {
+                None
+            };
+            describe(
+                species,
+                variety,
+                versions.as_slice(),
+                preferences,
+                &self.cleaner,
+            )
+        }
+        .boxed()
+    }
+}
 
-            if status.is_success() {
-                let PokemonSpecies {
+impl DescriptionsService for PokeApiService {
+    fn get_descriptions<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<PokemonDescriptions, PokemonServiceError>> {
+        async move {
+            let (
+                PokemonSpecies {
                     name,
                     flavor_text_entries,
-                } = response.json::<PokemonSpecies>().await?;
-                if let Some(description) = select_description(flavor_text_entries) {
-                    Ok(PokemonData { name, description })
-                } else {
-                    event!(Level::WARN, message = "No suitable description was available.", %name);
-                    Err(PokemonServiceError::NoSuchPokemon(name))
-                }
-            } else if status == StatusCode::NOT_FOUND {
-                Err(PokemonServiceError::NoSuchPokemon(name.to_string()))
+                },
+                variety,
+            ) = self.find_species(name).await?;
+            Ok(PokemonDescriptions {
+                name,
+                variety: variety.map(|Variety { name, .. }| name),
+                descriptions: all_descriptions(flavor_text_entries, &self.cleaner),
+            })
+        }
+        .boxed()
+    }
+}
+
+/// Large enough that all species are listed in a single page.
+const LIST_LIMIT: &str = "100000";
+
+impl SpeciesListService for PokeApiService {
+    fn list_species(&self) -> BoxFuture<'_, Result<Vec<String>, PokemonServiceError>> {
+        async move {
+            let PokeApiService {
+                client,
+                base_url,
+                retry,
+                ..
+            } = self;
+            event!(Level::DEBUG, message = "Requesting the list of Pokemon species.", url = %base_url);
+            let response = send_with_retry(retry, SERVICE_NAME, || {
+                client
+                    .get(base_url.clone())
+                    .query(&[("limit", LIST_LIMIT)])
+            })
+            .await?;
+            let status = response.status();
+            if status.is_success() {
+                let SpeciesList { results } = response.json::<SpeciesList>().await?;
+                Ok(results
+                    .into_iter()
+                    .map(|NamedResource { name }| name)
+                    .collect())
             } else {
                 event!(Level::ERROR, message = "Unanticipated response from Pokemon service.", %status);
                 Err(PokemonServiceError::ServiceUnavailable)
@@ -93,20 +310,191 @@ impl PokemonService for PokeApiService {
     }
 }
 
-/// Currently, we are only considering English descriptions.
+/// The path segment that identifies a species for the PokeAPI. Species can be requested by national
+/// Pokedex number (optionally zero-padded and prefixed with '#') or by name (either the display
+/// name or the name used by the PokeAPI).
+fn species_path_segment(name: &str) -> Option<String> {
+    if let Some(captures) = DEX_NUMBER.captures(name.trim()) {
+        captures[1]
+            .parse::<u32>()
+            .ok()
+            .filter(|number| *number > 0)
+            .map(|number| number.to_string())
+    } else {
+        Some(to_slug(name)).filter(|slug| NAME.is_match(slug))
+    }
+}
+
+/// Name of the remote service (for logging).
+const SERVICE_NAME: &str = "PokeAPI";
+
+/// If there is no description in any of the preferred languages, an English description is used.
 const ENGLISH: &str = "en";
 
-fn select_description(entries: Vec<FlavorEntry>) -> Option<String> {
-    // Chooses the last description that is in English.
-    entries
-        .into_iter()
-        .rev()
-        .find(|fl| fl.language.name == ENGLISH)
-        .map(|FlavorEntry { flavor_text, .. }| clean_flavor_text(flavor_text.as_str()))
+/// Create the response for a species (or a variety of it).
+///
+/// # Arguments
+/// * `species` - The species.
+/// * `variety` - The name of the variety, if one was requested.
+/// * `versions` - Versions of the game from which descriptions are preferred, the most preferred
+///   first.
+/// * `preferences` - The preferences of the request (for the languages of the description).
+/// * `cleaner` - Cleans the selected description.
+fn describe(
+    species: PokemonSpecies,
+    variety: Option<String>,
+    versions: &[String],
+    preferences: &DescriptionPreferences,
+    cleaner: &FlavorTextCleaner,
+) -> Result<PokemonData, PokemonServiceError> {
+    let PokemonSpecies {
+        name,
+        flavor_text_entries,
+    } = species;
+    let selected = select_description(
+        flavor_text_entries.as_slice(),
+        versions,
+        preferences.languages.as_slice(),
+    );
+    if let Some(FlavorEntry {
+        flavor_text,
+        language,
+        ..
+    }) = selected
+    {
+        Ok(PokemonData {
+            variety,
+            language: Some(language.name.clone()),
+            ..PokemonData::new(name, cleaner.clean(flavor_text.as_str()))
+        })
+    } else {
+        event!(Level::WARN, message = "No suitable description was available.", %name);
+        Err(PokemonServiceError::no_such_pokemon(&name))
+    }
+}
+
+/// Every distinct (cleaned) description, with the versions of the game in which each appeared. The
+/// descriptions are in the order in which they first appear.
+fn all_descriptions(entries: Vec<FlavorEntry>, cleaner: &FlavorTextCleaner) -> Vec<Description> {
+    let mut descriptions: Vec<Description> = vec![];
+    for FlavorEntry {
+        flavor_text,
+        language,
+        version,
+    } in entries
+    {
+        let text = cleaner.clean(flavor_text.as_str());
+        let existing = descriptions
+            .iter_mut()
+            .find(|d| d.language == language.name && d.description == text);
+        if let Some(existing) = existing {
+            if !existing.versions.contains(&version.name) {
+                existing.versions.push(version.name);
+            }
+        } else {
+            descriptions.push(Description {
+                language: language.name,
+                versions: vec![version.name],
+                description: text,
+                translated: false,
+                translator: None,
+                reason: None,
+            });
+        }
+    }
+    descriptions
+}
+
+/// Versions of the game, grouped by generation (oldest first).
+const GENERATIONS: [&[&str]; 9] = [
+    &["red", "blue", "yellow"],
+    &["gold", "silver", "crystal"],
+    &[
+        "ruby",
+        "sapphire",
+        "emerald",
+        "firered",
+        "leafgreen",
+        "colosseum",
+        "xd",
+    ],
+    &["diamond", "pearl", "platinum", "heartgold", "soulsilver"],
+    &["black", "white", "black-2", "white-2"],
+    &["x", "y", "omega-ruby", "alpha-sapphire"],
+    &[
+        "sun",
+        "moon",
+        "ultra-sun",
+        "ultra-moon",
+        "lets-go-pikachu",
+        "lets-go-eevee",
+    ],
+    &[
+        "sword",
+        "shield",
+        "the-isle-of-armor",
+        "the-crown-tundra",
+        "brilliant-diamond",
+        "shining-pearl",
+        "legends-arceus",
+    ],
+    &["scarlet", "violet", "the-teal-mask", "the-indigo-disk"],
+];
+
+/// The generation of a version of the game. Versions that are not known are assumed to have been
+/// released since this list was compiled, so are considered to be the newest.
+fn generation(version: &str) -> usize {
+    GENERATIONS
+        .iter()
+        .position(|versions| versions.contains(&version))
+        .unwrap_or(GENERATIONS.len())
+}
+
+/// Whether a requested language (for example "de-DE") matches the language of a description (for
+/// example "de"). Languages are matched either exactly or by their primary subtag.
+fn language_matches(requested: &str, language: &str) -> bool {
+    requested.eq_ignore_ascii_case(language)
+        || requested
+            .split('-')
+            .next()
+            .map(|primary| primary.eq_ignore_ascii_case(language))
+            .unwrap_or(false)
 }
 
-fn clean_flavor_text(text: &str) -> String {
-    // The descriptions returned by the service contain the original new-lines and line-feeds that
-    // we don't want.
-    WS.replace_all(text, " ").into()
+fn select_description<'a>(
+    entries: &'a [FlavorEntry],
+    versions: &[String],
+    languages: &[String],
+) -> Option<&'a FlavorEntry> {
+    // Chooses the first of the preferred languages (falling back to English) for which there are
+    // any descriptions. The description is taken from the first of the preferred versions for
+    // which there is one. Otherwise, the description from the newest version is chosen (taking
+    // the last in the list if there are several from the same generation).
+    let in_language = languages
+        .iter()
+        .map(String::as_str)
+        .chain(std::iter::once(ENGLISH))
+        .map(|requested| {
+            entries
+                .iter()
+                .filter(|fl| language_matches(requested, fl.language.name.as_str()))
+                .collect::<Vec<_>>()
+        })
+        .find(|candidates| !candidates.is_empty())?;
+    versions
+        .iter()
+        .find_map(|version| {
+            in_language
+                .iter()
+                .rev()
+                .find(|fl| fl.version.name == *version)
+        })
+        .or_else(|| {
+            in_language
+                .iter()
+                .enumerate()
+                .max_by_key(|(i, fl)| (generation(fl.version.name.as_str()), *i))
+                .map(|(_, fl)| fl)
+        })
+        .copied()
 }
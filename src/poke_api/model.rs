@@ -7,6 +7,40 @@ pub struct PokemonSpecies {
     pub flavor_text_entries: Vec<FlavorEntry>,
 }
 
+/// Models the response from the Pokemon endpoint of the PokeAPI (a variety of a species).
+#[derive(Deserialize, Debug)]
+pub struct Pokemon {
+    pub name: String,
+    pub species: NamedResource,
+    pub forms: Vec<NamedResource>,
+}
+
+/// Models the response from the Pokemon form endpoint of the PokeAPI.
+#[derive(Deserialize, Debug)]
+pub struct PokemonForm {
+    pub name: String,
+    pub pokemon: NamedResource,
+    /// The version group in which the form was introduced.
+    pub version_group: NamedResource,
+}
+
+/// Models the response from the version group endpoint of the PokeAPI.
+#[derive(Deserialize, Debug)]
+pub struct VersionGroup {
+    pub versions: Vec<NamedResource>,
+}
+
+/// Models the response from the (paginated) list endpoint for Pokemon species of the PokeAPI.
+#[derive(Deserialize, Debug)]
+pub struct SpeciesList {
+    pub results: Vec<NamedResource>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NamedResource {
+    pub name: String,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Language {
     pub name: String,
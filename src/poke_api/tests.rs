@@ -1,5 +1,7 @@
 use crate::poke_api::model::{FlavorEntry, Language, PokemonSpecies, Version};
-use crate::poke_api::PokeApiService;
+use crate::poke_api::{FlavorTextCleaner, PokeApiService};
+use crate::retry::RetryPolicy;
+use crate::services::pokemon::DescriptionPreferences;
 use reqwest::{Client, Url};
 
 const SAMPLE: &str = include_str!("sample.json");
@@ -31,7 +33,8 @@ fn pick_description() {
         make_flavor("fr", "Description 5", "c"),
     ];
 
-    let selected = super::select_description(descriptions);
+    let selected =
+        super::select_description(&descriptions, &[], &[]).map(|fl| fl.flavor_text.clone());
 
     assert_eq!(selected, Some("Description 4".to_string()));
 }
@@ -50,17 +53,100 @@ fn make_flavor(language: &str, description: &str, ver: &str) -> FlavorEntry {
 
 #[test]
 fn clean_description() {
-    let cleaned = super::clean_flavor_text(RAW_DESC);
+    let cleaned = FlavorTextCleaner::default().clean(RAW_DESC);
     assert_eq!(cleaned, EXPECTED_DESC);
 }
 
+#[test]
+fn clean_hyphenation() {
+    let cleaner = FlavorTextCleaner::default();
+    assert_eq!(cleaner.clean("sur\u{AD}\nroundings"), "surroundings");
+    assert_eq!(cleaner.clean("anger\u{AD}ed"), "angered");
+    assert_eq!(
+        cleaner.clean("its bolt-\nshaped tail"),
+        "its bolt-shaped tail"
+    );
+    assert_eq!(cleaner.clean("pent-up\x0cenergy"), "pent-up energy");
+    assert_eq!(cleaner.clean(" A - B\n"), "A - B");
+}
+
+#[test]
+fn normalise_casing() {
+    let cleaner = FlavorTextCleaner {
+        normalise_casing: true,
+    };
+    assert_eq!(
+        cleaner.clean("A POKéMON in a POKé BALL, in the POKéDEX."),
+        "A Pokémon in a Poké Ball, in the Pokédex."
+    );
+}
+
+/// Flavor text from the PokeAPI with the expected results of cleaning it.
+const CORPUS: &str = include_str!("flavor_text_corpus.json");
+
+#[derive(serde::Deserialize)]
+struct CorpusEntry {
+    version: String,
+    raw: String,
+    cleaned: String,
+    normalised: String,
+}
+
+#[test]
+fn clean_corpus() {
+    let corpus = serde_json::from_str::<Vec<CorpusEntry>>(CORPUS).unwrap();
+    let cleaner = FlavorTextCleaner::default();
+    let normaliser = FlavorTextCleaner {
+        normalise_casing: true,
+    };
+    for CorpusEntry {
+        version,
+        raw,
+        cleaned,
+        normalised,
+    } in corpus
+    {
+        assert_eq!(cleaner.clean(raw.as_str()), cleaned, "Version: {}", version);
+        assert_eq!(
+            normaliser.clean(raw.as_str()),
+            normalised,
+            "Version: {}",
+            version
+        );
+    }
+}
+
+#[test]
+fn clean_all_samples() {
+    let PokemonSpecies {
+        flavor_text_entries,
+        ..
+    } = serde_json::from_str::<PokemonSpecies>(SAMPLE).unwrap();
+    let cleaner = FlavorTextCleaner::default();
+    for FlavorEntry { flavor_text, .. } in flavor_text_entries {
+        let cleaned = cleaner.clean(flavor_text.as_str());
+        assert!(
+            !cleaned.contains(|c: char| c == '\u{AD}' || (c.is_whitespace() && c != ' ')),
+            "{:?}",
+            cleaned
+        );
+        assert!(!cleaned.contains("  "), "{:?}", cleaned);
+        assert_eq!(cleaner.clean(cleaned.as_str()), cleaned);
+    }
+}
+
 const SERVICE_URL: &str = "https://pokeapi.co/api/v2/pokemon-species";
 
 #[test]
 fn format_url() {
     let url = Url::parse(SERVICE_URL).unwrap();
 
-    let service = PokeApiService::new(Client::new(), url);
+    let service = PokeApiService::new(
+        Client::new(),
+        url,
+        RetryPolicy::default(),
+        FlavorTextCleaner::default(),
+    );
 
     let result = service.try_format_url("pikachu").map(|u| u.to_string());
 
@@ -87,16 +173,433 @@ async fn call_service() {
 
     let url = Url::parse(SERVICE_URL).unwrap();
 
-    let service = PokeApiService::new(Client::new(), url);
-    let result = service.get_pokemon("pikachu").await;
+    let service = PokeApiService::new(
+        Client::new(),
+        url,
+        RetryPolicy::default(),
+        FlavorTextCleaner::default(),
+    );
+    let result = service
+        .get_pokemon("pikachu", &DescriptionPreferences::default())
+        .await;
 
     match result {
-        Ok(PokemonData { name, description }) => {
+        Ok(PokemonData {
+            name, description, ..
+        }) => {
             assert_eq!(name, "pikachu");
-            assert_eq!(super::clean_flavor_text(description.as_str()), description);
+            assert_eq!(
+                FlavorTextCleaner::default().clean(description.as_str()),
+                description
+            );
         }
         Err(e) => {
             panic!("{:?}", e);
         }
     }
 }
+
+#[test]
+fn species_by_number() {
+    assert_eq!(
+        super::species_path_segment("pikachu"),
+        Some("pikachu".to_string())
+    );
+    assert_eq!(super::species_path_segment("25"), Some("25".to_string()));
+    assert_eq!(super::species_path_segment("025"), Some("25".to_string()));
+    assert_eq!(super::species_path_segment("#025"), Some("25".to_string()));
+    assert_eq!(super::species_path_segment("0"), None);
+    assert_eq!(super::species_path_segment("#"), None);
+    assert_eq!(super::species_path_segment("25a"), None);
+    assert_eq!(super::species_path_segment("99999999999"), None);
+}
+
+#[test]
+fn species_by_display_name() {
+    assert_eq!(
+        super::species_path_segment("Mr. Mime"),
+        Some("mr-mime".to_string())
+    );
+    assert_eq!(
+        super::species_path_segment("Nidoran♀"),
+        Some("nidoran-f".to_string())
+    );
+    assert_eq!(super::species_path_segment("Pika/chu"), None);
+    assert_eq!(super::species_path_segment("..."), None);
+}
+
+#[tokio::test]
+async fn get_pokemon_by_number() {
+    use crate::services::pokemon::PokemonService;
+    use warp::Filter;
+
+    let route = warp::path!("pokemon-species" / "25")
+        .map(|| warp::reply::with_header(SAMPLE, "content-type", "application/json"));
+    let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    let url = Url::parse(&format!("http://{}/pokemon-species", addr)).unwrap();
+
+    let service = PokeApiService::new(
+        Client::new(),
+        url,
+        RetryPolicy::default(),
+        FlavorTextCleaner::default(),
+    );
+    let result = service
+        .get_pokemon("#025", &DescriptionPreferences::default())
+        .await
+        .unwrap();
+
+    assert_eq!(result.name, "pikachu");
+}
+
+#[tokio::test]
+async fn list_species() {
+    use crate::services::pokemon::SpeciesListService;
+    use warp::Filter;
+
+    let route = warp::path!("pokemon-species")
+        .and(warp::query::<std::collections::HashMap<String, String>>())
+        .map(|query: std::collections::HashMap<String, String>| {
+            assert!(query.contains_key("limit"));
+            warp::reply::json(&serde_json::json!({
+                "count": 2,
+                "results": [
+                    { "name": "bulbasaur", "url": "https://pokeapi.co/api/v2/pokemon-species/1/" },
+                    { "name": "ivysaur", "url": "https://pokeapi.co/api/v2/pokemon-species/2/" }
+                ]
+            }))
+        });
+    let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    let url = Url::parse(&format!("http://{}/pokemon-species", addr)).unwrap();
+
+    let service = PokeApiService::new(
+        Client::new(),
+        url,
+        RetryPolicy::default(),
+        FlavorTextCleaner::default(),
+    );
+
+    assert_eq!(
+        service.list_species().await,
+        Ok(vec!["bulbasaur".to_string(), "ivysaur".to_string()])
+    );
+}
+
+#[test]
+fn pick_description_from_preferred_versions() {
+    let descriptions = vec![
+        make_flavor("en", "Description 1", "red"),
+        make_flavor("en", "Description 2", "sun"),
+        make_flavor("fr", "Description 3", "moon"),
+        make_flavor("en", "Description 4", "sword"),
+    ];
+    let versions = vec!["sun".to_string(), "moon".to_string()];
+
+    let selected = super::select_description(&descriptions, versions.as_slice(), &[])
+        .map(|fl| fl.flavor_text.clone());
+
+    assert_eq!(selected, Some("Description 2".to_string()));
+}
+
+#[test]
+fn pick_description_from_newest_version() {
+    let descriptions = vec![
+        make_flavor("en", "Description 1", "sword"),
+        make_flavor("en", "Description 2", "red"),
+        make_flavor("en", "Description 3", "x"),
+        make_flavor("fr", "Description 4", "scarlet"),
+    ];
+
+    let selected =
+        super::select_description(&descriptions, &[], &[]).map(|fl| fl.flavor_text.clone());
+
+    assert_eq!(selected, Some("Description 1".to_string()));
+}
+
+#[test]
+fn pick_description_falls_back_through_versions() {
+    let descriptions = vec![
+        make_flavor("en", "Description 1", "red"),
+        make_flavor("en", "Description 2", "x"),
+        make_flavor("en", "Description 3", "sword"),
+    ];
+    let versions = vec!["ultra-sun".to_string(), "red".to_string(), "x".to_string()];
+
+    let selected = super::select_description(&descriptions, versions.as_slice(), &[])
+        .map(|fl| fl.flavor_text.clone());
+
+    assert_eq!(selected, Some("Description 1".to_string()));
+}
+
+#[test]
+fn pick_description_by_language() {
+    let descriptions = vec![
+        make_flavor("en", "Description 1", "red"),
+        make_flavor("de", "Description 2", "red"),
+        make_flavor("ja-Hrkt", "Description 3", "red"),
+        make_flavor("de", "Description 4", "sword"),
+    ];
+    let select = |languages: &[&str]| {
+        let languages = languages
+            .iter()
+            .map(|language| language.to_string())
+            .collect::<Vec<_>>();
+        super::select_description(&descriptions, &[], languages.as_slice())
+            .map(|fl| fl.flavor_text.clone())
+    };
+
+    assert_eq!(select(&["fr", "de"]), Some("Description 4".to_string()));
+    // Regional variants match by their primary subtag.
+    assert_eq!(select(&["de-DE"]), Some("Description 4".to_string()));
+    assert_eq!(select(&["ja-hrkt"]), Some("Description 3".to_string()));
+    // English is the last resort.
+    assert_eq!(select(&["ko"]), Some("Description 1".to_string()));
+}
+
+#[test]
+fn description_language() {
+    let species = PokemonSpecies {
+        name: "pikachu".to_string(),
+        flavor_text_entries: vec![
+            make_flavor("en", "Description 1", "red"),
+            make_flavor("de", "Description 2", "red"),
+        ],
+    };
+    let preferences = DescriptionPreferences {
+        versions: vec![],
+        languages: vec!["de-at".to_string()],
+    };
+
+    let data = super::describe(
+        species,
+        None,
+        &[],
+        &preferences,
+        &FlavorTextCleaner::default(),
+    )
+    .unwrap();
+
+    assert_eq!(data.description, "Description 2");
+    assert_eq!(data.language, Some("de".to_string()));
+}
+
+/// Serve fixed responses for the species, Pokemon, form and version group endpoints. Anything
+/// else is not found.
+fn serve_varieties() -> Url {
+    use serde_json::json;
+    use warp::http::StatusCode;
+    use warp::Filter;
+
+    let flavor = |text: &str, version: &str| {
+        json!({
+            "flavor_text": text,
+            "language": { "name": "en" },
+            "version": { "name": version }
+        })
+    };
+    let resources = vec![
+        (
+            "pokemon-species/vulpix",
+            json!({
+                "name": "vulpix",
+                "flavor_text_entries": [
+                    flavor("Red description.", "red"),
+                    flavor("Sun description.", "sun"),
+                    flavor("Sword description.", "sword")
+                ]
+            }),
+        ),
+        (
+            "pokemon-species/unown",
+            json!({
+                "name": "unown",
+                "flavor_text_entries": [flavor("Unown description.", "gold")]
+            }),
+        ),
+        (
+            "pokemon/vulpix-alola",
+            json!({
+                "name": "vulpix-alola",
+                "species": { "name": "vulpix" },
+                "forms": [{ "name": "vulpix-alola" }]
+            }),
+        ),
+        (
+            "pokemon/unown",
+            json!({
+                "name": "unown",
+                "species": { "name": "unown" },
+                "forms": [{ "name": "unown-a" }, { "name": "unown-b" }]
+            }),
+        ),
+        (
+            "pokemon-form/vulpix-alola",
+            json!({
+                "name": "vulpix-alola",
+                "pokemon": { "name": "vulpix-alola" },
+                "version_group": { "name": "sun-moon" }
+            }),
+        ),
+        (
+            "pokemon-form/unown-b",
+            json!({
+                "name": "unown-b",
+                "pokemon": { "name": "unown" },
+                "version_group": { "name": "gold-silver" }
+            }),
+        ),
+        (
+            "version-group/sun-moon",
+            json!({ "versions": [{ "name": "sun" }, { "name": "moon" }] }),
+        ),
+    ]
+    .into_iter()
+    .map(|(path, body)| (path.to_string(), body))
+    .collect::<std::collections::HashMap<_, _>>();
+
+    let route = warp::path!(String / String).map(move |endpoint: String, name: String| {
+        match resources.get(&format!("{}/{}", endpoint, name)) {
+            Some(body) => warp::reply::with_status(warp::reply::json(body), StatusCode::OK),
+            None => warp::reply::with_status(warp::reply::json(&json!({})), StatusCode::NOT_FOUND),
+        }
+    });
+    let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    Url::parse(&format!("http://{}/pokemon-species", addr)).unwrap()
+}
+
+#[tokio::test]
+async fn get_pokemon_variety() {
+    use crate::model::PokemonData;
+    use crate::services::pokemon::PokemonService;
+
+    let service = PokeApiService::new(
+        Client::new(),
+        serve_varieties(),
+        RetryPolicy::default(),
+        FlavorTextCleaner::default(),
+    );
+
+    let PokemonData {
+        name,
+        variety,
+        description,
+        ..
+    } = service
+        .get_pokemon("Vulpix-Alola", &DescriptionPreferences::default())
+        .await
+        .unwrap();
+    assert_eq!(name, "vulpix");
+    assert_eq!(variety, Some("vulpix-alola".to_string()));
+    assert_eq!(description, "Sun description.");
+
+    // The species itself has no variety and the latest description.
+    let PokemonData {
+        variety,
+        description,
+        ..
+    } = service
+        .get_pokemon("vulpix", &DescriptionPreferences::default())
+        .await
+        .unwrap();
+    assert_eq!(variety, None);
+    assert_eq!(description, "Sword description.");
+
+    // Explicitly preferred versions take precedence over those of the form.
+    let preferences = DescriptionPreferences {
+        versions: vec!["red".to_string()],
+        languages: vec![],
+    };
+    let PokemonData { description, .. } = service
+        .get_pokemon("vulpix-alola", &preferences)
+        .await
+        .unwrap();
+    assert_eq!(description, "Red description.");
+}
+
+#[tokio::test]
+async fn get_pokemon_form() {
+    use crate::model::PokemonData;
+    use crate::services::pokemon::{PokemonService, PokemonServiceError};
+
+    let service = PokeApiService::new(
+        Client::new(),
+        serve_varieties(),
+        RetryPolicy::default(),
+        FlavorTextCleaner::default(),
+    );
+
+    let PokemonData {
+        name,
+        variety,
+        description,
+        ..
+    } = service
+        .get_pokemon("unown-b", &DescriptionPreferences::default())
+        .await
+        .unwrap();
+    assert_eq!(name, "unown");
+    assert_eq!(variety, Some("unown-b".to_string()));
+    assert_eq!(description, "Unown description.");
+
+    assert_eq!(
+        service
+            .get_pokemon("unown-z", &DescriptionPreferences::default())
+            .await,
+        Err(PokemonServiceError::no_such_pokemon("unown-z"))
+    );
+}
+
+#[test]
+fn all_descriptions_deduplicated() {
+    let PokemonSpecies {
+        flavor_text_entries,
+        ..
+    } = serde_json::from_str::<PokemonSpecies>(SAMPLE).unwrap();
+
+    let descriptions = super::all_descriptions(flavor_text_entries, &FlavorTextCleaner::default());
+
+    // Red and blue have the same description.
+    let red = descriptions
+        .iter()
+        .find(|d| d.language == "en" && d.versions.contains(&"red".to_string()))
+        .unwrap();
+    assert_eq!(red.versions, vec!["red", "blue"]);
+    assert_eq!(red.description, EXPECTED_DESC);
+
+    let mut texts = descriptions
+        .iter()
+        .map(|d| (d.language.as_str(), d.description.as_str()))
+        .collect::<Vec<_>>();
+    let count = texts.len();
+    texts.sort_unstable();
+    texts.dedup();
+    assert_eq!(texts.len(), count);
+}
+
+#[tokio::test]
+async fn get_variety_descriptions() {
+    use crate::services::pokemon::DescriptionsService;
+
+    let service = PokeApiService::new(
+        Client::new(),
+        serve_varieties(),
+        RetryPolicy::default(),
+        FlavorTextCleaner::default(),
+    );
+
+    let result = service.get_descriptions("vulpix-alola").await.unwrap();
+    assert_eq!(result.name, "vulpix");
+    assert_eq!(result.variety, Some("vulpix-alola".to_string()));
+    let texts = result
+        .descriptions
+        .iter()
+        .map(|d| d.description.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        texts,
+        vec!["Red description.", "Sun description.", "Sword description."]
+    );
+}
@@ -0,0 +1,113 @@
+use rand::Rng;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::collections::HashSet;
+use std::time::Duration;
+use tracing::{event, Level};
+
+#[cfg(test)]
+mod tests;
+
+/// Policy for retrying failed requests to a remote service, with exponential backoff.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts that will be made for a request (including the first).
+    pub max_attempts: u32,
+    /// The delay before the first retry. This is doubled for each subsequent retry.
+    pub base_delay: Duration,
+    /// The proportion (between 0 and 1) of each delay that is randomized.
+    pub jitter: f64,
+    /// Responses with these status codes will be retried.
+    pub retryable: HashSet<StatusCode>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            jitter: 0.5,
+            retryable: DEFAULT_RETRYABLE.iter().copied().collect(),
+        }
+    }
+}
+
+/// By default, only retry where the status code indicates that the problem is likely to be
+/// transient.
+const DEFAULT_RETRYABLE: [StatusCode; 3] = [
+    StatusCode::BAD_GATEWAY,
+    StatusCode::SERVICE_UNAVAILABLE,
+    StatusCode::GATEWAY_TIMEOUT,
+];
+
+impl RetryPolicy {
+    /// The delay (before jitter is applied) after the specified (1-based) attempt.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.base_delay.saturating_mul(factor)
+    }
+
+    /// The longest time that sending a request can take (including the delays between retries),
+    /// if every attempt fails after the specified timeout.
+    pub fn max_duration(&self, timeout: Duration) -> Duration {
+        (1..self.max_attempts).fold(
+            timeout.saturating_mul(self.max_attempts.max(1)),
+            |total, attempt| total.saturating_add(self.backoff(attempt)),
+        )
+    }
+
+    fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self.backoff(attempt);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter > 0.0 {
+            let scale = 1.0 - jitter * rand::thread_rng().gen::<f64>();
+            backoff.mul_f64(scale)
+        } else {
+            backoff
+        }
+    }
+
+    fn is_retryable_error(&self, error: &reqwest::Error) -> bool {
+        error.is_connect() || error.is_timeout() || error.is_request()
+    }
+}
+
+/// Send a request, retrying according to the policy. As the request might need to be sent more
+/// than once, a function to create it is required.
+///
+/// # Arguments
+/// * `policy` - The retry policy.
+/// * `service` - Name of the remote service (for logging).
+/// * `make_request` - Creates the request to send.
+pub async fn send_with_retry<F>(
+    policy: &RetryPolicy,
+    service: &str,
+    make_request: F,
+) -> Result<Response, reqwest::Error>
+where
+    F: Fn() -> RequestBuilder,
+{
+    let mut attempt = 1;
+    loop {
+        event!(Level::DEBUG, message = "Sending request.", %service, attempt);
+        let result = make_request().send().await;
+        let retry = attempt < policy.max_attempts
+            && match &result {
+                Ok(response) => policy.retryable.contains(&response.status()),
+                Err(error) => policy.is_retryable_error(error),
+            };
+        if !retry {
+            return result;
+        }
+        let delay = policy.delay(attempt);
+        match &result {
+            Ok(response) => {
+                event!(Level::WARN, message = "Request failed. Retrying.", %service, attempt, status = %response.status(), delay_ms = delay.as_millis() as u64);
+            }
+            Err(error) => {
+                event!(Level::WARN, message = "Request failed. Retrying.", %service, attempt, %error, delay_ms = delay.as_millis() as u64);
+            }
+        }
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
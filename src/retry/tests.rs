@@ -0,0 +1,109 @@
+use crate::retry::{send_with_retry, RetryPolicy};
+use reqwest::{Client, StatusCode};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use warp::Filter;
+
+fn policy(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        base_delay: Duration::from_millis(1),
+        jitter: 0.0,
+        ..Default::default()
+    }
+}
+
+/// Start a local server that responds with the specified status for the first `failures`
+/// requests and then succeeds.
+fn start_server(failures: usize, status: StatusCode) -> (SocketAddr, Arc<AtomicUsize>) {
+    let count = Arc::new(AtomicUsize::new(0));
+    let server_count = count.clone();
+    let route = warp::any().map(move || {
+        let n = server_count.fetch_add(1, Ordering::SeqCst);
+        let status = if n < failures { status } else { StatusCode::OK };
+        warp::reply::with_status("", status)
+    });
+    let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    (addr, count)
+}
+
+#[test]
+fn exponential_backoff() {
+    let policy = RetryPolicy {
+        base_delay: Duration::from_millis(100),
+        jitter: 0.0,
+        ..Default::default()
+    };
+
+    assert_eq!(policy.delay(1), Duration::from_millis(100));
+    assert_eq!(policy.delay(2), Duration::from_millis(200));
+    assert_eq!(policy.delay(3), Duration::from_millis(400));
+}
+
+#[test]
+fn max_duration() {
+    let policy = RetryPolicy {
+        base_delay: Duration::from_millis(100),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        policy.max_duration(Duration::from_millis(1000)),
+        Duration::from_millis(3300)
+    );
+    assert_eq!(policy.max_duration(Duration::MAX), Duration::MAX);
+}
+
+#[test]
+fn jitter_bounds() {
+    let policy = RetryPolicy {
+        base_delay: Duration::from_millis(100),
+        jitter: 0.5,
+        ..Default::default()
+    };
+
+    for _ in 0..100 {
+        let delay = policy.delay(2);
+        assert!(delay >= Duration::from_millis(100));
+        assert!(delay <= Duration::from_millis(200));
+    }
+}
+
+#[tokio::test]
+async fn retry_until_success() {
+    let (addr, count) = start_server(2, StatusCode::SERVICE_UNAVAILABLE);
+    let client = Client::new();
+    let url = format!("http://{}/", addr);
+
+    let result = send_with_retry(&policy(3), "test", || client.get(url.as_str())).await;
+
+    assert_eq!(result.unwrap().status(), StatusCode::OK);
+    assert_eq!(count.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn give_up_after_max_attempts() {
+    let (addr, count) = start_server(5, StatusCode::BAD_GATEWAY);
+    let client = Client::new();
+    let url = format!("http://{}/", addr);
+
+    let result = send_with_retry(&policy(3), "test", || client.get(url.as_str())).await;
+
+    assert_eq!(result.unwrap().status(), StatusCode::BAD_GATEWAY);
+    assert_eq!(count.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn do_not_retry_other_statuses() {
+    let (addr, count) = start_server(1, StatusCode::NOT_FOUND);
+    let client = Client::new();
+    let url = format!("http://{}/", addr);
+
+    let result = send_with_retry(&policy(3), "test", || client.get(url.as_str())).await;
+
+    assert_eq!(result.unwrap().status(), StatusCode::NOT_FOUND);
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+}
@@ -1,14 +1,17 @@
-use crate::model::PokemonData;
+use crate::model::{PokemonData, PokemonDescriptions};
 use futures::future::BoxFuture;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PokemonServiceError {
     /// The service could not produce a result for any reason other than the record not existing.
     ServiceUnavailable,
-    /// There is no Pokemon with the specified name available to the service.
-    NoSuchPokemon(String),
+    /// There is no Pokemon with the specified name available to the service. Names of Pokemon that
+    /// are similar to the requested name may be suggested (the most similar first).
+    NoSuchPokemon(String, Vec<String>),
+    /// The service did not respond in time.
+    Timeout,
 }
 
 impl Display for PokemonServiceError {
@@ -17,20 +20,83 @@ impl Display for PokemonServiceError {
             PokemonServiceError::ServiceUnavailable => {
                 write!(f, "The Pokemon service is not currently available.",)
             }
-            PokemonServiceError::NoSuchPokemon(name) => {
+            PokemonServiceError::NoSuchPokemon(name, _) => {
                 write!(f, "There is no Pokemon with name:  \"{}\".", name)
             }
+            PokemonServiceError::Timeout => {
+                write!(f, "The Pokemon service did not respond in time.")
+            }
         }
     }
 }
 
 impl Error for PokemonServiceError {}
 
+impl PokemonServiceError {
+    /// There is no Pokemon with the specified name (and there are no suggested alternatives).
+    pub fn no_such_pokemon(name: &str) -> Self {
+        PokemonServiceError::NoSuchPokemon(name.to_string(), vec![])
+    }
+}
+
+/// Preferences for choosing between the many descriptions of a species (from different versions
+/// of the game and in different languages).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct DescriptionPreferences {
+    /// Names of versions of the game (for example "sword"), the most preferred first.
+    pub versions: Vec<String>,
+    /// Language codes (for example "de" or "ja-Hrkt"), the most preferred first. If there is no
+    /// description in any of them, an English description is used.
+    pub languages: Vec<String>,
+}
+
+impl DescriptionPreferences {
+    /// Parse a comma separated list of names (for example "sword,shield,ultra-sun").
+    pub fn parse_list(list: &str) -> Vec<String> {
+        list.split(',')
+            .map(|item| item.trim().to_lowercase())
+            .filter(|item| !item.is_empty())
+            .collect()
+    }
+}
+
+impl Display for DescriptionPreferences {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "versions={}&languages={}",
+            self.versions.join(","),
+            self.languages.join(",")
+        )
+    }
+}
+
 /// A service that can provide descriptions of Pokemon species given their name.
 pub trait PokemonService {
     /// Attempt to get the description for a Pokemon with the specified species name.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the Pokemon.
+    /// * `preferences` - Preferences for which of the descriptions of the Pokemon is chosen.
     fn get_pokemon<'a>(
         &'a self,
         name: &'a str,
+        preferences: &'a DescriptionPreferences,
     ) -> BoxFuture<'a, Result<PokemonData, PokemonServiceError>>;
 }
+
+/// A service that can provide every description of a Pokemon species given its name.
+pub trait DescriptionsService {
+    /// Attempt to get all of the (untranslated) descriptions for a Pokemon with the specified
+    /// species name.
+    fn get_descriptions<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<PokemonDescriptions, PokemonServiceError>>;
+}
+
+/// A service that can list the names of all known Pokemon species.
+pub trait SpeciesListService {
+    /// Attempt to get the names of all of the species.
+    fn list_species(&self) -> BoxFuture<'_, Result<Vec<String>, PokemonServiceError>>;
+}
@@ -1,13 +1,49 @@
 use futures::future::BoxFuture;
+use futures::{FutureExt, TryFutureExt};
+use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
 
-#[derive(Debug, PartialEq, Eq)]
+/// The language of the text that the translation services accept.
+pub const SOURCE_LANGUAGE: &str = "en";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TranslationError {
     /// The service could not produce a result for any reason other than the record not existing.
     ServiceUnavailable,
     /// It was not possibe to translate the text.
     TranslationFailed,
+    /// The service has refused to translate the text as too many requests have been made.
+    RateLimited(RateLimit),
+    /// The service did not respond in time.
+    Timeout,
+}
+
+impl TranslationError {
+    /// Whether the translator could not be reached or refused to translate the text (so that
+    /// another translator, or the untranslated text, may be used instead), rather than failing to
+    /// translate it.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            TranslationError::ServiceUnavailable
+            | TranslationError::RateLimited(_)
+            | TranslationError::Timeout => true,
+            TranslationError::TranslationFailed => false,
+        }
+    }
+}
+
+/// Details of a rate limit that has been exceeded (where these are known).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateLimit {
+    /// How long to wait before making another request.
+    pub retry_after: Option<Duration>,
+    /// The number of requests permitted in the rate limit window.
+    pub limit: Option<u64>,
+    /// The number of requests remaining in the rate limit window.
+    pub remaining: Option<u64>,
 }
 
 impl Display for TranslationError {
@@ -17,12 +53,34 @@ impl Display for TranslationError {
                 write!(f, "The translation service is currently unavailable.",)
             }
             TranslationError::TranslationFailed => write!(f, "The text could not be translated."),
+            TranslationError::Timeout => {
+                write!(f, "The translation service did not respond in time.")
+            }
+            TranslationError::RateLimited(RateLimit { retry_after, .. }) => {
+                if let Some(retry_after) = retry_after {
+                    write!(
+                        f,
+                        "The translation service rate limit has been exceeded. Retry after {} seconds.",
+                        retry_after.as_secs()
+                    )
+                } else {
+                    write!(f, "The translation service rate limit has been exceeded.")
+                }
+            }
         }
     }
 }
 
 impl Error for TranslationError {}
 
+/// A translation along with the name of the translator that produced it (if the service reports
+/// it).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Translated {
+    pub text: String,
+    pub translator: Option<String>,
+}
+
 /// A service to translate the descriptions for Pokemon species.
 pub trait TranslationService {
     /// Attempt to translate a description.
@@ -30,4 +88,112 @@ pub trait TranslationService {
         &'a self,
         text: &'a str,
     ) -> BoxFuture<'a, Result<String, TranslationError>>;
+
+    /// Attempt to translate a description, reporting which translator produced the translation.
+    /// Services that combine other translators should override this.
+    fn attributed_translation<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<Translated, TranslationError>> {
+        self.attempt_translation(text)
+            .map_ok(|text| Translated {
+                text,
+                translator: None,
+            })
+            .boxed()
+    }
+
+    /// The translator expected to produce the translations reported by `attributed_translation`
+    /// (if the service reports it). Translations from any other translator are fallbacks.
+    fn primary_translator(&self) -> Option<&str> {
+        None
+    }
+
+    /// Whether a translation came from a fallback rather than the primary translator.
+    fn is_fallback(&self, translated: &Translated) -> bool {
+        match (self.primary_translator(), translated.translator.as_deref()) {
+            (Some(primary), Some(translator)) => primary != translator,
+            _ => false,
+        }
+    }
+}
+
+/// A translation service of any type.
+pub type DynTranslationService = Box<dyn TranslationService + Send + Sync>;
+
+impl<T: TranslationService + ?Sized> TranslationService for Box<T> {
+    fn attempt_translation<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<String, TranslationError>> {
+        (**self).attempt_translation(text)
+    }
+
+    fn attributed_translation<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<Translated, TranslationError>> {
+        (**self).attributed_translation(text)
+    }
+
+    fn primary_translator(&self) -> Option<&str> {
+        (**self).primary_translator()
+    }
+}
+
+/// A translation service along with the name of the style of translation that it performs.
+pub struct Translator<T> {
+    pub style: String,
+    pub service: Arc<T>,
+}
+
+impl<T> Clone for Translator<T> {
+    fn clone(&self) -> Self {
+        Translator {
+            style: self.style.clone(),
+            service: self.service.clone(),
+        }
+    }
+}
+
+/// Translation services, keyed by the name of the style of translation that they perform (for
+/// example "shakespeare" or "yoda").
+pub struct TranslatorRegistry<T> {
+    default_style: String,
+    translators: HashMap<String, Arc<T>>,
+}
+
+impl<T> TranslatorRegistry<T> {
+    /// # Arguments
+    /// * `default_style` The style of translation to use when none is requested.
+    /// * `default` The translation service for the default style.
+    pub fn new(default_style: &str, default: T) -> Self {
+        let mut translators = HashMap::new();
+        translators.insert(default_style.to_string(), Arc::new(default));
+        TranslatorRegistry {
+            default_style: default_style.to_string(),
+            translators,
+        }
+    }
+
+    /// Add the translation service for a style, replacing any existing service for that style.
+    pub fn register(&mut self, style: &str, translator: T) {
+        self.translators
+            .insert(style.to_string(), Arc::new(translator));
+    }
+
+    /// Get the translator for a style (case is ignored) or for the default style if none is
+    /// requested.
+    pub fn get(&self, style: Option<&str>) -> Option<Translator<T>> {
+        let style = style.map_or_else(|| self.default_style.clone(), str::to_lowercase);
+        let service = self.translators.get(&style)?.clone();
+        Some(Translator { style, service })
+    }
+
+    /// The names of all of the available styles, in alphabetical order.
+    pub fn styles(&self) -> Vec<String> {
+        let mut styles = self.translators.keys().cloned().collect::<Vec<_>>();
+        styles.sort();
+        styles
+    }
 }
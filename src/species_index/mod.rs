@@ -0,0 +1,210 @@
+use crate::model::{PokemonData, PokemonDescriptions};
+use crate::names::to_slug;
+use crate::services::pokemon::{
+    DescriptionPreferences, DescriptionsService, PokemonService, PokemonServiceError,
+    SpeciesListService,
+};
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{event, Level};
+
+#[cfg(test)]
+mod tests;
+
+/// The maximum number of suggestions to make for an unknown name.
+const MAX_SUGGESTIONS: usize = 5;
+
+/// A suggestion is only considered to be a confident match if it is the only name within this
+/// edit distance of the requested name.
+const MAX_CONFIDENT_DISTANCE: usize = 2;
+
+/// If fetching the list of species fails, how long to wait before trying again (unless the
+/// refresh period is shorter).
+const RETRY_PERIOD: Duration = Duration::from_secs(60);
+
+/// Names of similar species, for an unknown name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Suggestions {
+    /// Similar names, the most similar first.
+    pub ranked: Vec<String>,
+    /// If exactly one name is within `MAX_CONFIDENT_DISTANCE`, that name.
+    pub confident: Option<String>,
+}
+
+/// Local index of the names of all Pokemon species, used to suggest alternatives for names that
+/// could not be found.
+#[derive(Debug, Default)]
+pub struct SpeciesIndex {
+    names: RwLock<Vec<String>>,
+}
+
+impl SpeciesIndex {
+    /// Replace the contents of the index.
+    pub fn replace(&self, names: Vec<String>) {
+        *self.names.write().unwrap() = names;
+    }
+
+    /// Find the names that are similar to a requested name, by edit distance. Names are only
+    /// suggested if they are within a distance that depends on the length of the requested name.
+    pub fn suggest(&self, name: &str) -> Suggestions {
+        let slug = to_slug(name);
+        let max_distance = max_distance(slug.as_str());
+        if max_distance == 0 {
+            return Suggestions::default();
+        }
+
+        let names = self.names.read().unwrap();
+        let mut candidates = names
+            .iter()
+            .map(|candidate| (strsim::levenshtein(slug.as_str(), candidate), candidate))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .collect::<Vec<_>>();
+        candidates.sort();
+
+        let mut close = candidates
+            .iter()
+            .filter(|(distance, _)| *distance <= MAX_CONFIDENT_DISTANCE);
+        let confident = match (close.next(), close.next()) {
+            (Some((_, name)), None) => Some((*name).clone()),
+            _ => None,
+        };
+        let ranked = candidates
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, name)| name.clone())
+            .collect();
+        Suggestions { ranked, confident }
+    }
+}
+
+/// The edit distance within which names are considered to be similar. Short names need to be
+/// closer for the suggestions to be useful.
+fn max_distance(slug: &str) -> usize {
+    match slug.chars().count() {
+        0..=2 => 0,
+        3..=5 => 1,
+        6..=8 => 2,
+        _ => 3,
+    }
+}
+
+/// Keep the index up to date, fetching the list of species immediately and then periodically.
+///
+/// # Arguments
+/// * `index` - The index to populate.
+/// * `source` - Service providing the list of species.
+/// * `period` - How often to refresh the index.
+pub fn spawn_refresh<S>(index: Arc<SpeciesIndex>, source: S, period: Duration)
+where
+    S: SpeciesListService + Send + Sync + 'static,
+{
+    tokio::spawn(async move {
+        loop {
+            let delay = match source.list_species().await {
+                Ok(names) => {
+                    event!(
+                        Level::INFO,
+                        message = "Refreshed the index of Pokemon species.",
+                        count = names.len()
+                    );
+                    index.replace(names);
+                    period
+                }
+                Err(error) => {
+                    event!(Level::WARN, message = "Failed to refresh the index of Pokemon species.", %error);
+                    period.min(RETRY_PERIOD)
+                }
+            };
+            tokio::time::sleep(delay).await;
+        }
+    });
+}
+
+/// Wraps a Pokemon service so that, when a name cannot be found, similar names from the index are
+/// suggested. Optionally, if there is a single confident match, that Pokemon is returned instead.
+pub struct SuggestingPokemonService<S> {
+    inner: S,
+    index: Arc<SpeciesIndex>,
+    auto_resolve: bool,
+}
+
+impl<S> SuggestingPokemonService<S> {
+    /// # Arguments
+    /// * `inner` - The underlying service.
+    /// * `index` - Index of the names of all species.
+    /// * `auto_resolve` - Whether to return the Pokemon for a single confident match.
+    pub fn new(inner: S, index: Arc<SpeciesIndex>, auto_resolve: bool) -> Self {
+        SuggestingPokemonService {
+            inner,
+            index,
+            auto_resolve,
+        }
+    }
+}
+
+impl<S> PokemonService for SuggestingPokemonService<S>
+where
+    S: PokemonService + Send + Sync,
+{
+    fn get_pokemon<'a>(
+        &'a self,
+        name: &'a str,
+        preferences: &'a DescriptionPreferences,
+    ) -> BoxFuture<'a, Result<PokemonData, PokemonServiceError>> {
+        async move {
+            let SuggestingPokemonService {
+                inner,
+                index,
+                auto_resolve,
+            } = self;
+            match inner.get_pokemon(name, preferences).await {
+                Err(PokemonServiceError::NoSuchPokemon(missing, _)) => {
+                    let Suggestions { ranked, confident } = index.suggest(name);
+                    match confident {
+                        Some(resolved) if *auto_resolve => {
+                            event!(Level::INFO, message = "Resolved an unknown name to a similar name.", %name, %resolved);
+                            inner.get_pokemon(resolved.as_str(), preferences).await
+                        }
+                        _ => Err(PokemonServiceError::NoSuchPokemon(missing, ranked)),
+                    }
+                }
+                ow => ow,
+            }
+        }
+        .boxed()
+    }
+}
+
+impl<S> DescriptionsService for SuggestingPokemonService<S>
+where
+    S: DescriptionsService + Send + Sync,
+{
+    fn get_descriptions<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<PokemonDescriptions, PokemonServiceError>> {
+        async move {
+            let SuggestingPokemonService {
+                inner,
+                index,
+                auto_resolve,
+            } = self;
+            match inner.get_descriptions(name).await {
+                Err(PokemonServiceError::NoSuchPokemon(missing, _)) => {
+                    let Suggestions { ranked, confident } = index.suggest(name);
+                    match confident {
+                        Some(resolved) if *auto_resolve => {
+                            event!(Level::INFO, message = "Resolved an unknown name to a similar name.", %name, %resolved);
+                            inner.get_descriptions(resolved.as_str()).await
+                        }
+                        _ => Err(PokemonServiceError::NoSuchPokemon(missing, ranked)),
+                    }
+                }
+                ow => ow,
+            }
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,124 @@
+use crate::model::PokemonData;
+use crate::services::pokemon::{DescriptionPreferences, PokemonService, PokemonServiceError};
+use crate::species_index::{SpeciesIndex, SuggestingPokemonService, Suggestions};
+use futures::future::{ready, BoxFuture};
+use futures::FutureExt;
+use std::sync::Arc;
+
+const SPECIES: [&str; 8] = [
+    "bulbasaur",
+    "ivysaur",
+    "venusaur",
+    "pikachu",
+    "raichu",
+    "pichu",
+    "mew",
+    "mewtwo",
+];
+
+fn make_index() -> Arc<SpeciesIndex> {
+    let index = SpeciesIndex::default();
+    index.replace(SPECIES.iter().map(|name| name.to_string()).collect());
+    Arc::new(index)
+}
+
+#[test]
+fn suggest_single_match() {
+    let index = make_index();
+
+    assert_eq!(
+        index.suggest("pikachoo"),
+        Suggestions {
+            ranked: vec!["pikachu".to_string()],
+            confident: Some("pikachu".to_string()),
+        }
+    );
+}
+
+#[test]
+fn suggest_ranked() {
+    let index = make_index();
+
+    let Suggestions { ranked, confident } = index.suggest("piichu");
+    assert_eq!(
+        ranked,
+        vec![
+            "pichu".to_string(),
+            "pikachu".to_string(),
+            "raichu".to_string()
+        ]
+    );
+    // Pikachu is also a close match, so pichu is not confident.
+    assert_eq!(confident, None);
+
+    // Equally close matches are ordered by name and neither is confident.
+    let Suggestions { ranked, confident } = index.suggest("paichu");
+    assert_eq!(ranked, vec!["pichu".to_string(), "raichu".to_string()]);
+    assert_eq!(confident, None);
+}
+
+#[test]
+fn no_suggestions() {
+    let index = make_index();
+
+    assert_eq!(index.suggest("charizard"), Suggestions::default());
+    // Very short names are too ambiguous for suggestions.
+    assert_eq!(index.suggest("mw"), Suggestions::default());
+    assert_eq!(
+        SpeciesIndex::default().suggest("pikachoo"),
+        Suggestions::default()
+    );
+}
+
+/// Fake service that only knows about Pikachu.
+struct FakePokeService;
+
+impl PokemonService for FakePokeService {
+    fn get_pokemon<'a>(
+        &'a self,
+        name: &'a str,
+        _preferences: &'a DescriptionPreferences,
+    ) -> BoxFuture<'a, Result<PokemonData, PokemonServiceError>> {
+        ready(if name == "pikachu" {
+            Ok(PokemonData::new(
+                "pikachu".to_string(),
+                "A description.".to_string(),
+            ))
+        } else {
+            Err(PokemonServiceError::no_such_pokemon(name))
+        })
+        .boxed()
+    }
+}
+
+#[tokio::test]
+async fn suggestions_in_error() {
+    let service = SuggestingPokemonService::new(FakePokeService, make_index(), false);
+
+    assert_eq!(
+        service
+            .get_pokemon("pikachoo", &DescriptionPreferences::default())
+            .await,
+        Err(PokemonServiceError::NoSuchPokemon(
+            "pikachoo".to_string(),
+            vec!["pikachu".to_string()]
+        ))
+    );
+}
+
+#[tokio::test]
+async fn auto_resolve() {
+    let service = SuggestingPokemonService::new(FakePokeService, make_index(), true);
+
+    let result = service
+        .get_pokemon("pikachoo", &DescriptionPreferences::default())
+        .await;
+    assert_eq!(result.map(|data| data.name), Ok("pikachu".to_string()));
+
+    assert_eq!(
+        service
+            .get_pokemon("charizard", &DescriptionPreferences::default())
+            .await,
+        Err(PokemonServiceError::no_such_pokemon("charizard"))
+    );
+}
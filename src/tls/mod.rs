@@ -0,0 +1,205 @@
+use futures::Stream;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::rustls::crypto::ring::sign::any_supported_type;
+use tokio_rustls::rustls::server::{ClientHello, ResolvesServerCert};
+use tokio_rustls::rustls::sign::CertifiedKey;
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+use tracing::{event, Level};
+
+#[cfg(test)]
+mod tests;
+
+/// Connections that have not completed the TLS handshake within this time are dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum number of connections that have completed the TLS handshake but have not yet been
+/// taken by the server.
+const ACCEPT_BACKLOG: usize = 64;
+
+/// Locations of the certificate chain and private key (both PEM encoded) for serving HTTPS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+/// Errors that can occur loading the certificate chain and private key.
+#[derive(Debug)]
+pub enum TlsError {
+    /// A file could not be read.
+    Io(PathBuf, io::Error),
+    /// The certificate file did not contain any certificates.
+    NoCertificates(PathBuf),
+    /// The key file did not contain a private key.
+    NoPrivateKey(PathBuf),
+    /// The private key is not of a supported type.
+    InvalidKey(PathBuf),
+}
+
+impl Display for TlsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TlsError::Io(path, error) => write!(f, "Failed to read {}: {}", path.display(), error),
+            TlsError::NoCertificates(path) => {
+                write!(f, "No certificates were found in {}.", path.display())
+            }
+            TlsError::NoPrivateKey(path) => {
+                write!(f, "No private key was found in {}.", path.display())
+            }
+            TlsError::InvalidKey(path) => {
+                write!(f, "The private key in {} is not supported.", path.display())
+            }
+        }
+    }
+}
+
+impl Error for TlsError {}
+
+fn open(path: &Path) -> Result<BufReader<File>, TlsError> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|e| TlsError::Io(path.to_path_buf(), e))
+}
+
+fn load_certified_key(config: &TlsConfig) -> Result<CertifiedKey, TlsError> {
+    let TlsConfig {
+        cert_path,
+        key_path,
+    } = config;
+
+    let certs = rustls_pemfile::certs(&mut open(cert_path)?)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| TlsError::Io(cert_path.clone(), e))?;
+    if certs.is_empty() {
+        return Err(TlsError::NoCertificates(cert_path.clone()));
+    }
+
+    let key = rustls_pemfile::private_key(&mut open(key_path)?)
+        .map_err(|e| TlsError::Io(key_path.clone(), e))?
+        .ok_or_else(|| TlsError::NoPrivateKey(key_path.clone()))?;
+    let signing_key =
+        any_supported_type(&key).map_err(|_| TlsError::InvalidKey(key_path.clone()))?;
+
+    Ok(CertifiedKey::new(certs, signing_key))
+}
+
+/// Provides the certificate for each TLS handshake. The certificate can be reloaded from disk,
+/// without affecting the listener; only connections established after the reload will use the
+/// new certificate.
+#[derive(Debug)]
+pub struct ReloadableCertResolver {
+    config: TlsConfig,
+    current: RwLock<Arc<CertifiedKey>>,
+}
+
+impl ReloadableCertResolver {
+    /// Load the initial certificate chain and private key.
+    pub fn load(config: TlsConfig) -> Result<Self, TlsError> {
+        let certified_key = load_certified_key(&config)?;
+        Ok(ReloadableCertResolver {
+            config,
+            current: RwLock::new(Arc::new(certified_key)),
+        })
+    }
+
+    /// Reload the certificate chain and private key. If this fails, the previous certificate
+    /// will continue to be used.
+    pub fn reload(&self) -> Result<(), TlsError> {
+        let certified_key = load_certified_key(&self.config)?;
+        *self.current.write().unwrap() = Arc::new(certified_key);
+        Ok(())
+    }
+
+    /// Create an acceptor for TLS connections that will use the current certificate.
+    pub fn acceptor(self: &Arc<Self>) -> TlsAcceptor {
+        let mut config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_cert_resolver(self.clone());
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        TlsAcceptor::from(Arc::new(config))
+    }
+}
+
+impl ResolvesServerCert for ReloadableCertResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.read().unwrap().clone())
+    }
+}
+
+/// Reload the certificate each time the process receives a SIGHUP.
+#[cfg(unix)]
+pub fn reload_on_hangup(resolver: Arc<ReloadableCertResolver>) -> io::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = signal(SignalKind::hangup())?;
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            match resolver.reload() {
+                Ok(_) => {
+                    event!(Level::INFO, message = "Reloaded the TLS certificate.");
+                }
+                Err(error) => {
+                    event!(Level::ERROR, message = "Failed to reload the TLS certificate. The previous certificate will continue to be used.", %error);
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Accept TLS connections on a listener. Handshakes are performed concurrently so that a slow
+/// client cannot hold up the others. Connections for which the handshake fails are dropped.
+///
+/// # Arguments
+/// * `listener` - Listener for the underlying TCP connections.
+/// * `acceptor` - Performs the TLS handshake for each connection.
+pub fn incoming(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+) -> impl Stream<Item = io::Result<TlsStream<TcpStream>>> {
+    let (tx, rx) = mpsc::channel(ACCEPT_BACKLOG);
+
+    tokio::spawn(async move {
+        while !tx.is_closed() {
+            let (stream, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(error) => {
+                    // Typically caused by running out of file descriptors so back off briefly.
+                    event!(Level::ERROR, message = "Failed to accept a connection.", %error);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+            let acceptor = acceptor.clone();
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(tls_stream)) => {
+                        let _ = tx.send(tls_stream).await;
+                    }
+                    Ok(Err(error)) => {
+                        event!(Level::DEBUG, message = "TLS handshake failed.", %peer, %error);
+                    }
+                    Err(_) => {
+                        event!(Level::DEBUG, message = "TLS handshake timed out.", %peer);
+                    }
+                }
+            });
+        }
+    });
+
+    futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|stream| (Ok(stream), rx))
+    })
+}
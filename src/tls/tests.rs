@@ -0,0 +1,133 @@
+use crate::tls::{incoming, ReloadableCertResolver, TlsConfig, TlsError};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use warp::Filter;
+
+const TEST_DATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/tls/test_data");
+
+fn test_file(name: &str) -> PathBuf {
+    Path::new(TEST_DATA).join(name)
+}
+
+fn test_config(name: &str) -> TlsConfig {
+    TlsConfig {
+        cert_path: test_file(&format!("{}.crt", name)),
+        key_path: test_file(&format!("{}.key", name)),
+    }
+}
+
+/// Copy one of the test certificates to a fresh directory so that it can be replaced.
+fn install(dir_name: &str, name: &str) -> TlsConfig {
+    let dir = std::env::temp_dir().join(format!("pokeservice-{}-{}", dir_name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let config = TlsConfig {
+        cert_path: dir.join("server.crt"),
+        key_path: dir.join("server.key"),
+    };
+    replace(&config, name);
+    config
+}
+
+fn replace(config: &TlsConfig, name: &str) {
+    let source = test_config(name);
+    fs::copy(source.cert_path, &config.cert_path).unwrap();
+    fs::copy(source.key_path, &config.key_path).unwrap();
+}
+
+fn pem_certificate(name: &str) -> Vec<u8> {
+    let pem = fs::read(test_file(&format!("{}.crt", name))).unwrap();
+    let mut reader = pem.as_slice();
+    let cert = rustls_pemfile::certs(&mut reader).next().unwrap().unwrap();
+    cert.to_vec()
+}
+
+fn current_certificate(resolver: &ReloadableCertResolver) -> Vec<u8> {
+    resolver.current.read().unwrap().cert[0].to_vec()
+}
+
+#[test]
+fn load_certificate() {
+    let resolver = ReloadableCertResolver::load(test_config("first")).unwrap();
+    assert_eq!(current_certificate(&resolver), pem_certificate("first"));
+}
+
+#[test]
+fn load_errors() {
+    let missing = TlsConfig {
+        cert_path: test_file("missing.crt"),
+        key_path: test_file("first.key"),
+    };
+    assert!(matches!(
+        ReloadableCertResolver::load(missing),
+        Err(TlsError::Io(_, _))
+    ));
+
+    let no_certificates = TlsConfig {
+        cert_path: test_file("first.key"),
+        key_path: test_file("first.key"),
+    };
+    assert!(matches!(
+        ReloadableCertResolver::load(no_certificates),
+        Err(TlsError::NoCertificates(_))
+    ));
+
+    let no_key = TlsConfig {
+        cert_path: test_file("first.crt"),
+        key_path: test_file("first.crt"),
+    };
+    assert!(matches!(
+        ReloadableCertResolver::load(no_key),
+        Err(TlsError::NoPrivateKey(_))
+    ));
+}
+
+#[test]
+fn reload_certificate() {
+    let config = install("reload", "first");
+    let resolver = ReloadableCertResolver::load(config.clone()).unwrap();
+
+    replace(&config, "second");
+    resolver.reload().unwrap();
+    assert_eq!(current_certificate(&resolver), pem_certificate("second"));
+
+    // A failed reload keeps the previous certificate.
+    fs::remove_file(&config.key_path).unwrap();
+    assert!(resolver.reload().is_err());
+    assert_eq!(current_certificate(&resolver), pem_certificate("second"));
+}
+
+fn trusting_client(name: &str) -> reqwest::Client {
+    let pem = fs::read(test_file(&format!("{}.crt", name))).unwrap();
+    reqwest::Client::builder()
+        .add_root_certificate(reqwest::Certificate::from_pem(pem.as_slice()).unwrap())
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn serve_https() {
+    let config = install("serve", "first");
+    let resolver = Arc::new(ReloadableCertResolver::load(config.clone()).unwrap());
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!(
+        "https://localhost:{}/",
+        listener.local_addr().unwrap().port()
+    );
+    let server = warp::serve(warp::any().map(|| "Hello"))
+        .run_incoming(incoming(listener, resolver.acceptor()));
+    tokio::spawn(server);
+
+    let response = trusting_client("first").get(&url).send().await.unwrap();
+    assert_eq!(response.text().await.unwrap(), "Hello");
+
+    replace(&config, "second");
+    resolver.reload().unwrap();
+
+    // The listener is unaffected but new connections use the new certificate.
+    assert!(trusting_client("first").get(&url).send().await.is_err());
+    let response = trusting_client("second").get(&url).send().await.unwrap();
+    assert_eq!(response.text().await.unwrap(), "Hello");
+}
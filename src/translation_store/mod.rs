@@ -0,0 +1,92 @@
+use crate::services::translation::{TranslationError, TranslationService};
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use sled::{Db, IVec};
+use tracing::{event, Level};
+
+#[cfg(test)]
+mod tests;
+
+/// Translation service that records every successful translation from another service in a
+/// persistent store on disk. The delegate service is only called for text that has never been
+/// translated before, even if the process has been restarted in between.
+pub struct PersistentTranslationService<T> {
+    inner: T,
+    translator: String,
+    db: Db,
+}
+
+impl<T> PersistentTranslationService<T> {
+    /// # Arguments
+    /// * `inner` - The service that will provide translations that are not already stored.
+    /// * `translator` - Identifies the kind of translation performed by `inner`. Translations are
+    ///   keyed by this and the source text.
+    /// * `db` - The store for the translations.
+    pub fn new(inner: T, translator: &str, db: Db) -> Self {
+        PersistentTranslationService {
+            inner,
+            translator: translator.to_string(),
+            db,
+        }
+    }
+
+    fn key(&self, text: &str) -> Vec<u8> {
+        // The translator ID can't contain a null character so this is unambiguous.
+        let mut key = Vec::with_capacity(self.translator.len() + text.len() + 1);
+        key.extend_from_slice(self.translator.as_bytes());
+        key.push(0);
+        key.extend_from_slice(text.as_bytes());
+        key
+    }
+}
+
+impl<T> TranslationService for PersistentTranslationService<T>
+where
+    T: TranslationService + Send + Sync,
+{
+    fn attempt_translation<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<String, TranslationError>> {
+        async move {
+            let PersistentTranslationService {
+                inner,
+                translator,
+                db,
+            } = self;
+            let key = self.key(text);
+
+            match db.get(&key).map(|value| value.map(decode)) {
+                Ok(Some(Some(translated))) => {
+                    event!(Level::DEBUG, message = "Found stored translation.", %translator);
+                    return Ok(translated);
+                }
+                Ok(Some(None)) => {
+                    event!(Level::WARN, message = "Stored translation was not valid UTF-8.", %translator);
+                }
+                Ok(None) => {
+                    event!(Level::DEBUG, message = "No stored translation available.", %translator);
+                }
+                Err(error) => {
+                    event!(Level::WARN, message = "Failed to read from the translation store.", %error);
+                }
+            }
+
+            let translated = inner.attempt_translation(text).await?;
+
+            let stored = match db.insert(key, translated.as_bytes()) {
+                Ok(_) => db.flush_async().await.map(|_| ()),
+                Err(error) => Err(error),
+            };
+            if let Err(error) = stored {
+                event!(Level::WARN, message = "Failed to write to the translation store.", %error);
+            }
+            Ok(translated)
+        }
+        .boxed()
+    }
+}
+
+fn decode(value: IVec) -> Option<String> {
+    String::from_utf8(value.to_vec()).ok()
+}
@@ -0,0 +1,134 @@
+use crate::services::translation::{TranslationError, TranslationService};
+use crate::translation_store::PersistentTranslationService;
+use futures::future::{ready, BoxFuture};
+use futures::FutureExt;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Fake translator that counts how many times it is called.
+#[derive(Default, Clone)]
+struct CountingTranslator {
+    calls: Arc<AtomicUsize>,
+}
+
+impl CountingTranslator {
+    fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+impl TranslationService for CountingTranslator {
+    fn attempt_translation<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<String, TranslationError>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        ready(if text.is_empty() {
+            Err(TranslationError::TranslationFailed)
+        } else {
+            Ok(text.to_uppercase())
+        })
+        .boxed()
+    }
+}
+
+fn temporary_db() -> sled::Db {
+    sled::Config::new().temporary(true).open().unwrap()
+}
+
+#[tokio::test]
+async fn stored_translation_reused() {
+    let translator = CountingTranslator::default();
+    let service = PersistentTranslationService::new(translator.clone(), "upper", temporary_db());
+
+    let first = service.attempt_translation("text").await;
+    let second = service.attempt_translation("text").await;
+
+    assert_eq!(first, Ok("TEXT".to_string()));
+    assert_eq!(second, Ok("TEXT".to_string()));
+    assert_eq!(translator.calls(), 1);
+
+    let other = service.attempt_translation("other").await;
+    assert_eq!(other, Ok("OTHER".to_string()));
+    assert_eq!(translator.calls(), 2);
+}
+
+#[tokio::test]
+async fn failures_not_stored() {
+    let translator = CountingTranslator::default();
+    let service = PersistentTranslationService::new(translator.clone(), "upper", temporary_db());
+
+    let first = service.attempt_translation("").await;
+    let second = service.attempt_translation("").await;
+
+    assert_eq!(first, Err(TranslationError::TranslationFailed));
+    assert_eq!(second, Err(TranslationError::TranslationFailed));
+    assert_eq!(translator.calls(), 2);
+}
+
+#[tokio::test]
+async fn keyed_by_translator() {
+    let db = temporary_db();
+    let translator = CountingTranslator::default();
+    let first_service = PersistentTranslationService::new(translator.clone(), "first", db.clone());
+    let second_service = PersistentTranslationService::new(translator.clone(), "second", db);
+
+    assert_eq!(
+        first_service.attempt_translation("text").await,
+        Ok("TEXT".to_string())
+    );
+    assert_eq!(
+        second_service.attempt_translation("text").await,
+        Ok("TEXT".to_string())
+    );
+    assert_eq!(translator.calls(), 2);
+}
+
+/// Open the store at the path, waiting for any previous instance to release its lock (sled's
+/// flusher thread can hold it briefly after the database is dropped).
+async fn reopen(path: &Path) -> sled::Db {
+    let mut attempts = 0;
+    loop {
+        match sled::open(path) {
+            Ok(db) => return db,
+            Err(_) if attempts < 50 => {
+                attempts += 1;
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            Err(error) => panic!("Failed to reopen the store: {}", error),
+        }
+    }
+}
+
+#[tokio::test]
+async fn survives_restart() {
+    let path = std::env::temp_dir().join(format!(
+        "pokeservice-translation-store-{}",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&path);
+
+    let translator = CountingTranslator::default();
+    let service =
+        PersistentTranslationService::new(translator.clone(), "upper", sled::open(&path).unwrap());
+    assert_eq!(
+        service.attempt_translation("text").await,
+        Ok("TEXT".to_string())
+    );
+    drop(service);
+
+    // A new service (as after a restart) finds the translation in the reopened store.
+    let translator = CountingTranslator::default();
+    let service =
+        PersistentTranslationService::new(translator.clone(), "upper", reopen(&path).await);
+    assert_eq!(
+        service.attempt_translation("text").await,
+        Ok("TEXT".to_string())
+    );
+    assert_eq!(translator.calls(), 0);
+
+    drop(service);
+    std::fs::remove_dir_all(&path).unwrap();
+}